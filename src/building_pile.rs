@@ -0,0 +1,101 @@
+use std::fmt;
+
+use crate::{card::Card, ruleset::Ruleset};
+
+// A stack on the FIELD. It starts empty and has to be built up in order from 1 to the highest card
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BuildingPile {
+    // Cards lying on the stack, the last card being the visible one
+    pub cards: Vec<Card>,
+    // Value of the visible card, a wild counts as the value it replaces (0 if the stack is empty)
+    value: u8,
+    // Whether the visible card is a wild
+    wild_on_top: bool
+}
+
+impl BuildingPile {
+    pub fn new() -> Self {
+        BuildingPile::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.value == 0
+    }
+
+    // Value of the visible card, None if the stack is empty
+    pub fn top_value(&self) -> Option<u8> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.value)
+        }
+    }
+
+    pub fn wild_on_top(&self) -> bool {
+        self.wild_on_top
+    }
+
+    // Value the next card has to have. A stack that reached the highest card continues at 1
+    pub fn next_value(&self, highest_card: u8) -> u8 {
+        if self.value >= highest_card {
+            1
+        } else {
+            self.value + 1
+        }
+    }
+
+    pub fn is_complete(&self, highest_card: u8) -> bool {
+        self.value == highest_card
+    }
+
+    // Check if a card may be played onto the stack. A wild fits onto any stack, unless the ruleset forbids two wilds in a row
+    pub fn accepts(&self, card: Card, ruleset: &Ruleset) -> bool {
+        match card {
+            Card::Number(n) => n == self.next_value(ruleset.highest_card),
+            Card::Wild => ruleset.wild_on_wild || !self.wild_on_top
+        }
+    }
+
+    // Play a card onto the stack, a wild takes the next value
+    pub fn push(&mut self, card: Card, highest_card: u8) {
+        self.value = self.next_value(highest_card);
+        self.wild_on_top = card.is_wild();
+        self.cards.push(card);
+    }
+
+    // Take back the visible card, the card below becomes visible again
+    pub fn pop(&mut self, highest_card: u8) -> Option<Card> {
+        let card = self.cards.pop()?;
+
+        match self.cards.last() {
+            None => {
+                self.value = 0;
+                self.wild_on_top = false;
+            }
+            Some(below) => {
+                self.value = if self.value <= 1 { highest_card } else { self.value - 1 };
+                self.wild_on_top = below.is_wild();
+            }
+        }
+
+        Some(card)
+    }
+
+    // Remove every card from the stack and return them
+    pub fn clear(&mut self) -> Vec<Card> {
+        self.value = 0;
+        self.wild_on_top = false;
+        std::mem::take(&mut self.cards)
+    }
+}
+
+// The value of the visible card, marked with "*" if it is a wild. An empty stack is written as "-"
+impl fmt::Display for BuildingPile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.top_value() {
+            None => write!(f, "-"),
+            Some(v) if self.wild_on_top => write!(f, "{}*", v),
+            Some(v) => write!(f, "{}", v)
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt;
+
+// A Skip-Bo card: either a numbered card or a Skip-Bo card (wild) that can replace any number
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Card {
+    Number(u8),
+    Wild
+}
+
+impl Card {
+    pub fn is_wild(&self) -> bool {
+        *self == Card::Wild
+    }
+
+    // Value printed on the card, a wild has none
+    pub fn value(&self) -> Option<u8> {
+        match self {
+            Card::Number(n) => Some(*n),
+            Card::Wild => None
+        }
+    }
+}
+
+// A numbered card is written as its number, a wild as "SB"
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Card::Number(n) => write!(f, "{}", n),
+            Card::Wild => write!(f, "SB")
+        }
+    }
+}
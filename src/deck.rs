@@ -0,0 +1,40 @@
+use rand::{seq::SliceRandom, rngs::StdRng, SeedableRng};
+
+use crate::{ruleset::Ruleset, card::Card};
+
+// Build an unshuffled deck: every card from 1 to the highest card a number of times and the jokers.
+// With the official rules these are 162 cards: 144 numbered cards (12 x 1-12) and 18 jokers
+pub fn new_deck(ruleset: &Ruleset) -> Vec<Card> {
+    let mut deck: Vec<Card> = Vec::with_capacity(ruleset.card_copies * ruleset.highest_card as usize + ruleset.jokers);
+
+    for card in 1..=ruleset.highest_card {
+        deck.extend(std::iter::repeat_n(Card::Number(card), ruleset.card_copies));
+    }
+    deck.extend(std::iter::repeat_n(Card::Wild, ruleset.jokers));
+
+    deck
+}
+
+// The cards of a game before the first turn: every player's STACK, the draw pile in the order it is drawn from and the RNG
+// that shuffles the completed FIELD stacks back into it. One deal can start several games, ex: with the players in other seats
+#[derive(Debug, Clone)]
+pub struct Deal {
+    pub stacks: Vec<Vec<Card>>,
+    pub draw_pile: Vec<Card>,
+    pub seed: u64,
+    pub rng: StdRng
+}
+
+impl Deal {
+    // Shuffle the full deck and deal every player's STACK from it, the rest becomes the draw pile
+    pub fn new(ruleset: &Ruleset, players: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut draw_pile = new_deck(ruleset);
+        assert!(ruleset.stack_size * players <= draw_pile.len(), "a deck of {} cards cannot deal {} stacks of {} cards, see Ruleset::for_players", draw_pile.len(), players, ruleset.stack_size);
+        draw_pile.shuffle(&mut rng);
+
+        let stacks = (0..players).map(|_| draw_pile.split_off(draw_pile.len() - ruleset.stack_size)).collect();
+        Deal { stacks, draw_pile, seed, rng }
+    }
+}
@@ -0,0 +1,120 @@
+use rand::{Rng, seq::SliceRandom};
+
+use crate::{card::Card, deck::new_deck, game_view::GameView};
+
+// The cards a player cannot see, indexed by player number: every STACK below its visible card (bottom first),
+// the HANDs of the other players (his own is left empty) and the draw pile (top last)
+#[derive(Debug, PartialEq, Clone)]
+pub struct HiddenCards {
+    pub stacks: Vec<Vec<Card>>,
+    pub hands: Vec<Vec<Card>>,
+    pub draw_pile: Vec<Card>
+}
+
+// Every card of the deck that is not visible to the player
+pub fn unseen_cards(view: &GameView) -> Vec<Card> {
+    let mut visible: Vec<Card> = Vec::new();
+    visible.extend(view.hand);
+    visible.extend(view.side.iter().flatten());
+    visible.extend(view.stack_top);
+    visible.extend(view.playing_field.iter().flat_map(|p| &p.cards));
+    visible.extend(view.completed_cards);
+    for o in &view.opponents {
+        visible.extend(o.side.iter().flatten());
+        visible.extend(o.stack_top);
+    }
+
+    let mut unseen = new_deck(view.ruleset);
+    for card in visible {
+        if let Some(index) = unseen.iter().position(|c| *c == card) {
+            unseen.swap_remove(index);
+        }
+    }
+    unseen
+}
+
+impl HiddenCards {
+    // Deal the unseen cards at random onto the hidden places, every distribution consistent with the view is equally likely
+    pub fn sample<R: Rng>(view: &GameView, rng: &mut R) -> Self {
+        let mut unseen = unseen_cards(view);
+        unseen.shuffle(rng);
+
+        let player_count = view.opponents.len() + 1;
+        let mut hidden = HiddenCards { stacks: vec![Vec::new(); player_count], hands: vec![Vec::new(); player_count], draw_pile: Vec::new() };
+
+        let below_top = |stack_size: usize| stack_size.saturating_sub(1);
+        hidden.stacks[view.player_num as usize] = unseen.split_off(unseen.len() - below_top(view.stack_size));
+        for o in &view.opponents {
+            hidden.stacks[o.player_num as usize] = unseen.split_off(unseen.len() - below_top(o.stack_size));
+            hidden.hands[o.player_num as usize] = unseen.split_off(unseen.len() - o.hand_size);
+        }
+        hidden.draw_pile = unseen;
+
+        hidden
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+    use crate::{game::{Game, SkipBoGame}, players::{player::Player, good_player::GoodPlayer, simple_player::SimplePlayer}, ruleset::Ruleset};
+
+    fn sorted(mut cards: Vec<Card>) -> Vec<Option<u8>> {
+        cards.sort_by_key(|c| c.value());
+        cards.iter().map(|c| c.value()).collect()
+    }
+
+    #[test]
+    fn sampled_games_keep_what_the_player_sees() {
+        let players: Vec<Box<dyn Player>> = vec![Box::new(GoodPlayer::default()), Box::new(SimplePlayer {}), Box::new(GoodPlayer::default())];
+        let mut game = SkipBoGame::with_seed(Ruleset::official().for_players(3), 3, 7);
+        for _ in 0..12 {
+            for (index, player) in players.iter().enumerate() {
+                game.play(index as i8, player.as_ref());
+            }
+        }
+        assert!(!game.end);
+        game.refill_hand(1);
+        let view = game.view(1);
+        let unseen = sorted(unseen_cards(&view));
+
+        let mut rng = StdRng::seed_from_u64(3);
+        for seed in 0..20 {
+            let hidden = HiddenCards::sample(&view, &mut rng);
+            let mut dealt = hidden.draw_pile.clone();
+            dealt.extend(hidden.stacks.iter().chain(&hidden.hands).flatten());
+            assert_eq!(sorted(dealt), unseen, "the hidden cards are not the unseen ones");
+
+            let sampled = SkipBoGame::from_view(&view, hidden, seed);
+            let sampled_view = sampled.view(1);
+            assert_eq!(sampled_view.hand, view.hand);
+            assert_eq!(sampled_view.side, view.side);
+            assert_eq!((sampled_view.stack_top, sampled_view.stack_size), (view.stack_top, view.stack_size));
+            assert_eq!(sampled_view.playing_field, view.playing_field);
+            assert_eq!(sampled_view.completed_cards, view.completed_cards);
+            assert_eq!(sampled_view.draw_pile_size, view.draw_pile_size);
+            for (s, o) in sampled_view.opponents.iter().zip(&view.opponents) {
+                assert_eq!((s.player_num, s.stack_top, s.stack_size, s.hand_size, s.side), (o.player_num, o.stack_top, o.stack_size, o.hand_size, o.side));
+            }
+            assert_eq!(sampled.get_valid_moves(1), game.get_valid_moves(1));
+        }
+    }
+
+    #[test]
+    fn samples_do_not_depend_on_the_hidden_cards() {
+        let mut game = SkipBoGame::with_seed(Ruleset::official(), 2, 5);
+        game.refill_hand(0);
+        game.refill_hand(1);
+
+        // Swap a card of the opponent's HAND with a different one from the draw pile, the player sees the same game
+        let mut other = game.clone();
+        let card = other.players[1].hand[0];
+        let index = other.draw_pile.iter().position(|c| *c != card).unwrap();
+        std::mem::swap(&mut other.players[1].hand[0], &mut other.draw_pile[index]);
+        assert_ne!(other.players[1].hand, game.players[1].hand);
+
+        let sample = HiddenCards::sample(&game.view(0), &mut StdRng::seed_from_u64(9));
+        assert_eq!(HiddenCards::sample(&other.view(0), &mut StdRng::seed_from_u64(9)), sample);
+    }
+}
@@ -0,0 +1,177 @@
+use rand::Rng;
+
+use crate::{game::{Game, SkipBoGame}, deck::Deal, players::{player::Player, registry::{PlayerRegistry, PlayerSpec, RegistryError}}, record::GameRecord, ruleset::Ruleset, runner::Runner};
+
+// The games of one deal. In game r every player sits r seats further on than in the first game
+#[derive(Debug, PartialEq, Clone)]
+pub struct DealResult {
+    // The player that won each game, None for a draw
+    pub winners: Vec<Option<usize>>,
+    // The record of each game if the match keeps them, named after the players in their seats
+    pub records: Vec<GameRecord>
+}
+
+impl DealResult {
+    // The player that won every game of the deal, if one did
+    pub fn sweep(&self) -> Option<usize> {
+        self.winners[0].filter(|_| self.winners.iter().all(|w| *w == self.winners[0]))
+    }
+}
+
+// The results of every deal of a duplicate match, in the order of the deals
+#[derive(Debug, PartialEq, Clone)]
+pub struct DuplicateResults {
+    pub players: usize,
+    pub deals: Vec<DealResult>
+}
+
+impl DuplicateResults {
+    // Games won by every player
+    pub fn wins(&self) -> Vec<u64> {
+        let mut wins = vec![0; self.players];
+        for w in self.deals.iter().flat_map(|d| d.winners.iter().flatten()) {
+            wins[*w] += 1;
+        }
+        wins
+    }
+
+    // Deals won by every player from every seat
+    pub fn sweeps(&self) -> Vec<u64> {
+        let mut sweeps = vec![0; self.players];
+        for p in self.deals.iter().filter_map(|d| d.sweep()) {
+            sweeps[p] += 1;
+        }
+        sweeps
+    }
+
+    // Deals no player won from every seat, the cards decided them. Deals with a draw count as split
+    pub fn split(&self) -> u64 {
+        self.deals.iter().filter(|d| d.sweep().is_none()).count() as u64
+    }
+
+    // Games that ended without a winner
+    pub fn draws(&self) -> u64 {
+        self.deals.iter().flat_map(|d| &d.winners).filter(|w| w.is_none()).count() as u64
+    }
+}
+
+// Plays every deal once per player and moves every player one seat on after each game, so all players get the same STACKs
+// and draw pile from every seat. The deals are played in parallel on the runner, the results only depend on the seed
+pub struct DuplicateMatch<'a> {
+    registry: &'a PlayerRegistry,
+    pub players: Vec<PlayerSpec>,
+    pub ruleset: Ruleset,
+    // Keep the record of every game in the results
+    pub records: bool,
+    pub runner: Runner
+}
+
+impl<'a> DuplicateMatch<'a> {
+    pub fn new(registry: &'a PlayerRegistry, players: Vec<PlayerSpec>, ruleset: Ruleset) -> Self {
+        DuplicateMatch { registry, players, ruleset, records: false, runner: Runner::new() }
+    }
+
+    // The player at every seat in game r of a deal: player p sits at seat (p + r) % seats
+    pub fn seating(&self, rotation: usize) -> Vec<usize> {
+        let seats = self.players.len();
+        (0..seats).map(|s| (s + seats - rotation % seats) % seats).collect()
+    }
+
+    // A game of the deal, the STACKs go to the seats whoever sits there
+    fn start(&self, deal: &Deal) -> SkipBoGame {
+        SkipBoGame::from_deal(self.ruleset, (0..self.players.len() as i8).collect(), deal.clone())
+    }
+
+    // Play every game of a deal, every player keeps its seed in all of them
+    pub fn play_deal(&self, deal: &Deal, seeds: &[u64]) -> Result<DealResult, RegistryError> {
+        let mut result = DealResult { winners: Vec::new(), records: Vec::new() };
+        for rotation in 0..self.players.len() {
+            let seating = self.seating(rotation);
+            let players = seating.iter().map(|p| self.registry.build(&self.players[*p], seeds[*p])).collect::<Result<Vec<Box<dyn Player>>, _>>()?;
+
+            let mut game = self.start(deal);
+            game.play_out(&players);
+            result.winners.push((game.winner >= 0).then(|| seating[game.winner as usize]));
+            if self.records {
+                result.records.push(GameRecord::from_game(&game, seating.iter().map(|p| self.players[*p].to_string()).collect()));
+            }
+        }
+        Ok(result)
+    }
+
+    // Deal and play the deals, done is called after every deal
+    pub fn run(&self, deals: usize, seed: u64, done: impl Fn() + Sync) -> Result<DuplicateResults, RegistryError> {
+        let seats = self.players.len();
+        let results = self.runner.run(deals, seed, |_, rng| {
+            let deal = Deal::new(&self.ruleset, seats, rng.gen());
+            let seeds: Vec<u64> = (0..seats).map(|_| rng.gen()).collect();
+            self.play_deal(&deal, &seeds)
+        }, done);
+
+        Ok(DuplicateResults { players: seats, deals: results.into_iter().collect::<Result<_, _>>()? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duplicate_match(registry: &PlayerRegistry, players: usize) -> DuplicateMatch<'_> {
+        let specs = ["good", "simple", "bad"].iter().cycle().take(players).map(|s| s.parse().unwrap()).collect();
+        DuplicateMatch::new(registry, specs, Ruleset::official().for_players(players))
+    }
+
+    #[test]
+    fn every_rotation_deals_the_same_cards_to_a_seat() {
+        let registry = PlayerRegistry::new();
+        for seats in [2, 3, 5] {
+            let duplicate = duplicate_match(&registry, seats);
+            let deal = Deal::new(&duplicate.ruleset, seats, 11);
+
+            let hands = |game: &mut SkipBoGame| -> Vec<_> {
+                (0..seats as i8).for_each(|s| game.refill_hand(s));
+                game.players.iter().map(|p| (p.stack.clone(), p.hand.clone())).collect()
+            };
+            let first = hands(&mut duplicate.start(&deal));
+            for _ in 1..seats {
+                assert_eq!(hands(&mut duplicate.start(&deal)), first);
+            }
+
+            // Every player sits at every seat once
+            for p in 0..seats {
+                let mut seats_taken: Vec<usize> = (0..seats).map(|r| duplicate.seating(r).iter().position(|q| *q == p).unwrap()).collect();
+                seats_taken.sort_unstable();
+                assert_eq!(seats_taken, (0..seats).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn results_count_sweeps_and_draws() {
+        let results = DuplicateResults { players: 2, deals: vec![
+            DealResult { winners: vec![Some(1), Some(1)], records: Vec::new() },
+            DealResult { winners: vec![Some(0), Some(1)], records: Vec::new() },
+            DealResult { winners: vec![None, Some(0)], records: Vec::new() }
+        ] };
+        assert_eq!(results.wins(), vec![2, 3]);
+        assert_eq!(results.sweeps(), vec![0, 1]);
+        assert_eq!(results.split(), 2);
+        assert_eq!(results.draws(), 1);
+    }
+
+    #[test]
+    fn records_name_the_players_in_their_seats() {
+        let registry = PlayerRegistry::new();
+        let mut duplicate = duplicate_match(&registry, 2);
+        duplicate.records = true;
+        let result = duplicate.play_deal(&Deal::new(&duplicate.ruleset, 2, 5), &[1, 2]).unwrap();
+
+        assert_eq!(result.records[0].players, vec!["good", "simple"]);
+        assert_eq!(result.records[1].players, vec!["simple", "good"]);
+        // Both games replay from the same deal
+        assert!(result.records.iter().all(|r| r.seed == 5));
+        for (record, winner) in result.records.iter().zip(&result.winners) {
+            assert_eq!(winner.map(|w| record.players[record.winner as usize] == duplicate.players[w].to_string()), winner.map(|_| true));
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::{fmt, fs, io};
+
+use serde::{Serialize, Deserialize};
+
+use crate::{game::SkipBoGame, card::Card, players::good_player::{distance_between_cards, pile_value}};
+
+// Rates a game from the view of one player, higher is better. The ratings of different players of one game can be compared
+pub trait Evaluator {
+    fn evaluate(&self, game: &SkipBoGame, player_num: i8) -> f64;
+}
+
+// Any function of the game and a player number rates games as well
+impl<F: Fn(&SkipBoGame, i8) -> f64> Evaluator for F {
+    fn evaluate(&self, game: &SkipBoGame, player_num: i8) -> f64 {
+        self(game, player_num)
+    }
+}
+
+// One value for every feature of a game seen by a player, used for the features themselves and for their weights.
+// Distances are the cards missing on the closest FIELD stack before a visible STACK card could be played, as a fraction of the highest card
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Features {
+    // Cards on the STACKs of the team
+    pub stack_size: f64,
+    // Distance to the STACK card of every member of the team
+    pub pile_distance: f64,
+    // Distance to the STACK card of the next opponent, who plays on the FIELD the player leaves
+    pub next_opponent_distance: f64,
+    // Average distance to the STACK cards of all opponents
+    pub opponent_distance: f64,
+    // Cards on the STACKs of the other team that is closest to winning
+    pub opponent_stack_size: f64,
+    // Cards on SIDE lying on a lower card, they cannot be played before it
+    pub side_disorder: f64,
+    // Wilds in HAND
+    pub wilds: f64,
+    pub hand_size: f64
+}
+
+// Cards missing before the visible card of a player's STACK could be played, 0 if it is a wild or the STACK is empty
+fn stack_distance(game: &SkipBoGame, player_num: usize) -> f64 {
+    let highest_card = game.ruleset.highest_card;
+    let distance = match game.players[player_num].stack.last().and_then(|c| c.value()) {
+        None => 0,
+        // A FIELD stack showing the same value has to go around once more
+        Some(v) => game.playing_field.iter().map(|p| match distance_between_cards(pile_value(p, highest_card), v as i8, highest_card) {
+            0 => highest_card as i8 - 1,
+            d => d - 1
+        }).min().unwrap_or(0)
+    };
+    distance as f64 / highest_card as f64
+}
+
+impl Features {
+    pub const COUNT: usize = 8;
+    // The names of the features in the order they are declared, as they are written in JSON
+    pub const NAMES: [&'static str; Features::COUNT] = ["stack_size", "pile_distance", "next_opponent_distance", "opponent_distance", "opponent_stack_size", "side_disorder", "wilds", "hand_size"];
+
+    // The features of a game seen by a player
+    pub fn of(game: &SkipBoGame, player_num: i8) -> Self {
+        let player_count = game.players.len();
+        let team = game.teams[player_num as usize];
+        let members: Vec<usize> = (0..player_count).filter(|q| game.teams[*q] == team).collect();
+        let opponents: Vec<usize> = (0..player_count).filter(|q| game.teams[*q] != team).collect();
+        let next_opponent = (1..player_count).map(|i| (player_num as usize + i) % player_count).find(|q| game.teams[*q] != team);
+
+        let team_stack_size = |t: i8| (0..player_count).filter(|q| game.teams[*q] == t).map(|q| game.players[q].stack.len()).sum::<usize>();
+        let p = &game.players[player_num as usize];
+
+        Features {
+            stack_size: team_stack_size(team) as f64,
+            pile_distance: members.iter().map(|q| stack_distance(game, *q)).sum(),
+            next_opponent_distance: next_opponent.map_or(0.0, |o| stack_distance(game, o)),
+            opponent_distance: opponents.iter().map(|q| stack_distance(game, *q)).sum::<f64>() / opponents.len().max(1) as f64,
+            opponent_stack_size: opponents.iter().map(|q| team_stack_size(game.teams[*q])).min().unwrap_or(0) as f64,
+            side_disorder: p.side.iter().map(|side| side.windows(2).filter(|w| match (w[0].value(), w[1].value()) {
+                (Some(below), Some(above)) => above > below,
+                _ => false
+            }).count()).sum::<usize>() as f64,
+            wilds: p.hand.iter().filter(|c| **c == Card::Wild).count() as f64,
+            hand_size: p.hand.len() as f64
+        }
+    }
+
+    // The features in the order they are declared, ex: for a tuner working on plain vectors
+    pub fn to_vec(&self) -> Vec<f64> {
+        vec![self.stack_size, self.pile_distance, self.next_opponent_distance, self.opponent_distance, self.opponent_stack_size, self.side_disorder, self.wilds, self.hand_size]
+    }
+
+    pub fn from_slice(values: &[f64]) -> Self {
+        assert_eq!(values.len(), Features::COUNT, "a value is needed for every feature");
+        Features {
+            stack_size: values[0],
+            pile_distance: values[1],
+            next_opponent_distance: values[2],
+            opponent_distance: values[3],
+            opponent_stack_size: values[4],
+            side_disorder: values[5],
+            wilds: values[6],
+            hand_size: values[7]
+        }
+    }
+
+    pub fn dot(&self, other: &Features) -> f64 {
+        self.to_vec().iter().zip(other.to_vec()).map(|(a, b)| a * b).sum()
+    }
+}
+
+#[derive(Debug)]
+pub enum EvaluatorError {
+    Io(io::Error),
+    Json(serde_json::Error)
+}
+
+impl fmt::Display for EvaluatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvaluatorError::Io(error) => write!(f, "cannot access weights: {}", error),
+            EvaluatorError::Json(error) => write!(f, "invalid weights: {}", error)
+        }
+    }
+}
+
+impl std::error::Error for EvaluatorError {}
+
+impl From<io::Error> for EvaluatorError {
+    fn from(error: io::Error) -> Self {
+        EvaluatorError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for EvaluatorError {
+    fn from(error: serde_json::Error) -> Self {
+        EvaluatorError::Json(error)
+    }
+}
+
+// Rates a game by the weighted sum of its features. The weights are stored as JSON with one entry per feature,
+// features left out weigh 0. Ex: {"stack_size": -1.0, "pile_distance": -1.0}
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LinearEvaluator {
+    pub weights: Features
+}
+
+impl LinearEvaluator {
+    pub fn new(weights: Features) -> Self {
+        LinearEvaluator { weights }
+    }
+
+    // Only the cards the team has left count: its STACK cards and the distance to them
+    pub fn cards_left() -> Self {
+        LinearEvaluator::new(Features { stack_size: -1.0, pile_distance: -1.0, ..Features::default() })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.weights).expect("weights can be serialized")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, EvaluatorError> {
+        Ok(LinearEvaluator::new(serde_json::from_str(json)?))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), EvaluatorError> {
+        Ok(fs::write(path, self.to_json())?)
+    }
+
+    pub fn load(path: &str) -> Result<Self, EvaluatorError> {
+        LinearEvaluator::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+// The cards the team has left against keeping FIELD away from the next opponent's STACK card, which weighs twice as much.
+// Wilds kept in HAND are worth something later and SIDE stacks should go down in value
+impl Default for LinearEvaluator {
+    fn default() -> Self {
+        LinearEvaluator::new(Features {
+            stack_size: -1.0,
+            pile_distance: -1.0,
+            next_opponent_distance: 2.0,
+            side_disorder: -0.1,
+            wilds: 0.2,
+            ..Features::default()
+        })
+    }
+}
+
+impl Evaluator for LinearEvaluator {
+    fn evaluate(&self, game: &SkipBoGame, player_num: i8) -> f64 {
+        self.weights.dot(&Features::of(game, player_num))
+    }
+}
@@ -1,58 +1,276 @@
-use crate::{players::player::{Player, PlayerState}, move_stack::{Move, CardStack}};
-use rand::{Rng, prelude::ThreadRng};
+use crate::{players::player::{Player, PlayerState, NewPlayerState}, move_stack::{Move, CardStack, MoveError}, deck::Deal, ruleset::Ruleset, card::Card, building_pile::BuildingPile, game_view::{GameView, OpponentView}, history::GameEvent, determinization::HiddenCards};
+use rand::{seq::SliceRandom, rngs::StdRng, SeedableRng};
+
+// Number of turns after which a game that nobody won is called off as a draw
+pub const MAX_TURNS: i32 = 5000;
 
 pub trait Game {
-    fn new(players: Vec<PlayerState>) -> Self;
+    fn new(ruleset: Ruleset, player_num: i8) -> Self;
+    fn with_seed(ruleset: Ruleset, player_num: i8, seed: u64) -> Self;
+    fn with_teams(ruleset: Ruleset, teams: Vec<i8>, seed: u64) -> Self;
+    fn from_deal(ruleset: Ruleset, teams: Vec<i8>, deal: Deal) -> Self;
+    fn from_view(view: &GameView, hidden: HiddenCards, seed: u64) -> Self;
 
     // Modifying game state
     fn to_playing_field_from_stack(&mut self, player_num: i8, stack: i8);
     fn to_playing_field_from_hand(&mut self, player_num: i8, stack_field: i8, stack_hand: i8);
     fn to_playing_field_from_side(&mut self, player_num: i8, stack_field: i8, stack_side: i8);
     fn to_side(&mut self, player_num: i8, card: i8, stack: i8);
-    fn execute_move(&mut self, player_num: i8, m: &Move);
+    fn clear_completed_pile(&mut self, stack_field: i8);
+    fn validate_move(&self, player_num: i8, m: &Move) -> Result<(), MoveError>;
+    fn execute_move(&mut self, player_num: i8, m: &Move) -> Result<(), MoveError>;
+    fn discard(&mut self, player_num: i8, m: &Move) -> Result<(), MoveError>;
+    fn forfeit(&mut self, player_num: i8, m: &Move, error: MoveError);
+    fn draw_card(&mut self, player_num: i8) -> Option<Card>;
     fn refill_hand(&mut self, player_num: i8);
+    fn end_turn(&mut self, player_num: i8, idle: bool);
+
+    // History
+    fn undo(&mut self) -> bool;
+    fn redo(&mut self) -> bool;
 
     // Playing the game
-    fn get_valid_moves(&self, playing_field: [(i8, bool); 4], hand: Vec<i8>, side: [Vec<i8>; 4], stack: Vec<i8>) -> Vec<Move>;
-    fn play_move(&mut self, player_num: i8, player: &Box<dyn Player>) -> bool;
-    fn play(&mut self, player_num: i8, player: &Box<dyn Player>);
+    fn is_partner(&self, player_num: i8, other: i8) -> bool;
+    fn get_valid_moves(&self, player_num: i8) -> Vec<Move>;
+    fn view(&self, player_num: i8) -> GameView<'_>;
+    fn play_move(&mut self, player_num: i8, player: &dyn Player) -> bool;
+    fn play(&mut self, player_num: i8, player: &dyn Player);
+    fn continue_turn(&mut self, player_num: i8, player: &dyn Player);
     fn check_win(&mut self) -> bool;
+    fn play_out(&mut self, players: &[Box<dyn Player>]) -> i32;
 }
 
+#[derive(Clone)]
 pub struct SkipBoGame {
-    pub playing_field: [(i8, bool); 4],
+    pub ruleset: Ruleset,
+    pub playing_field: Vec<BuildingPile>,
+    // Cards of completed FIELD stacks, shuffled back into the draw pile once it runs out
+    pub completed_cards: Vec<Card>,
+    pub draw_pile: Vec<Card>,
     pub players: Vec<PlayerState>,
+    // Team number of every player. Partners can play from each other's STACK and SIDE and win together once all their STACKs are empty
+    pub teams: Vec<i8>,
+    // Number of turns started and moves executed so far and number of turns in a row in which no card was moved
+    pub turn: i32,
+    pub moves_played: i32,
+    pub idle_turns: i32,
     pub end: bool,
+    // The player that won, -1 while the game is on and if it ended in a draw
     pub winner: i8,
-    pub rng: ThreadRng
+    // Every event of the game in order and the groups of events taken back by undo, the last group being redone first
+    pub history: Vec<GameEvent>,
+    pub undone: Vec<Vec<GameEvent>>,
+    // Every card is shuffled by this RNG, so the same seed always replays the same game
+    pub seed: u64,
+    pub rng: StdRng
+}
+
+// Changes to the state that are not checked against the rules. Other modules change a game through validated moves and turns,
+// so its history always matches it
+impl SkipBoGame {
+    // Move the card of an executed Move-Object with the corresponding functions
+    fn apply_move(&mut self, player_num: i8, m: &Move) {
+        self.moves_played += 1;
+
+        if m.from == CardStack::Stack {
+            self.to_playing_field_from_stack(player_num, m.to_num);
+        }
+
+        if m.from == CardStack::Hand {
+            if m.to == CardStack::Field {
+                self.to_playing_field_from_hand(player_num, m.to_num, m.from_num);
+            } else if m.to == CardStack::Side {
+                self.to_side(player_num, m.from_num, m.to_num);
+            }
+        }
+
+        if m.from == CardStack::Side {
+            self.to_playing_field_from_side(player_num, m.to_num, m.from_num)
+        }
+
+        match m.from {
+            CardStack::PartnerStack(q) => self.to_playing_field_from_stack(q, m.to_num),
+            CardStack::PartnerSide(q) => self.to_playing_field_from_side(q, m.to_num, m.from_num),
+            _ => {}
+        }
+    }
+
+    // Put the card of an executed Move-Object back where it came from
+    fn revert_move(&mut self, player_num: i8, m: &Move) {
+        self.moves_played -= 1;
+
+        let card = match m.to {
+            CardStack::Field => self.playing_field[m.to_num as usize].pop(self.ruleset.highest_card),
+            _ => self.players[player_num as usize].side[m.to_num as usize].pop()
+        }.expect("the moved card is on top of its destination");
+
+        let owner = match m.from {
+            CardStack::PartnerStack(q) | CardStack::PartnerSide(q) => q,
+            _ => player_num
+        };
+        let p = &mut self.players[owner as usize];
+        match m.from {
+            CardStack::Stack | CardStack::PartnerStack(_) => p.stack.push(card),
+            CardStack::Hand => p.hand.insert(m.from_num as usize, card),
+            _ => p.side[m.from_num as usize].push(card)
+        }
+    }
+
+    // Apply a new event to the game and add it to the history. Events that were undone cannot be redone anymore
+    fn record(&mut self, event: GameEvent) {
+        self.apply_event(&event);
+        self.history.push(event);
+        self.undone.clear();
+    }
+
+    fn apply_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::MovePlayed { player_num, m, .. } => self.apply_move(*player_num, m),
+            GameEvent::CardDrawn { player_num, .. } => {
+                let card = self.draw_pile.pop().expect("draw pile is not empty");
+                self.players[*player_num as usize].hand.push(card);
+            }
+            GameEvent::PileCompleted { pile, .. } => {
+                let mut cards = self.playing_field[*pile as usize].clear();
+                self.completed_cards.append(&mut cards);
+            }
+            GameEvent::Reshuffled { drained, draw_pile, .. } => {
+                for (pile, cards) in self.playing_field.iter_mut().zip(drained) {
+                    pile.cards.drain(..cards.len());
+                }
+                self.completed_cards.clear();
+                self.draw_pile = draw_pile.clone();
+            }
+            GameEvent::Forfeited { player_num, error, .. } => {
+                self.players[*player_num as usize].forfeited = Some(*error);
+            }
+            GameEvent::TurnEnded { idle, idle_turns, .. } => {
+                self.turn += 1;
+                self.idle_turns = if *idle { idle_turns + 1 } else { 0 };
+            }
+        }
+    }
+
+    fn revert_event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::MovePlayed { player_num, m, .. } => self.revert_move(*player_num, m),
+            GameEvent::CardDrawn { player_num, .. } => {
+                let card = self.players[*player_num as usize].hand.pop().expect("drawn card is in hand");
+                self.draw_pile.push(card);
+            }
+            GameEvent::PileCompleted { pile, cards } => {
+                let new_len = self.completed_cards.len() - cards.len();
+                self.completed_cards.truncate(new_len);
+                for card in cards {
+                    self.playing_field[*pile as usize].push(*card, self.ruleset.highest_card);
+                }
+            }
+            GameEvent::Reshuffled { drained, completed, .. } => {
+                for (pile, cards) in self.playing_field.iter_mut().zip(drained) {
+                    pile.cards.splice(0..0, cards.iter().copied());
+                }
+                self.completed_cards = completed.clone();
+                self.draw_pile.clear();
+            }
+            GameEvent::Forfeited { player_num, .. } => {
+                self.players[*player_num as usize].forfeited = None;
+            }
+            GameEvent::TurnEnded { idle_turns, .. } => {
+                self.turn -= 1;
+                self.idle_turns = *idle_turns;
+            }
+        }
+    }
 }
 
 impl Game for SkipBoGame {
-    fn new(players: Vec<PlayerState>) -> Self {
+    // Start a game with a random seed
+    fn new(ruleset: Ruleset, player_num: i8) -> Self {
+        SkipBoGame::with_seed(ruleset, player_num, rand::random())
+    }
+
+    // Without team play every player is his own team
+    fn with_seed(ruleset: Ruleset, player_num: i8, seed: u64) -> Self {
+        SkipBoGame::with_teams(ruleset, (0..player_num).collect(), seed)
+    }
+
+    // One player for every team number, ex: [0, 1, 0, 1] seats partners across from each other
+    fn with_teams(ruleset: Ruleset, teams: Vec<i8>, seed: u64) -> Self {
+        let deal = Deal::new(&ruleset, teams.len(), seed);
+        SkipBoGame::from_deal(ruleset, teams, deal)
+    }
+
+    // Start a game from cards that were dealt before, the STACKs go to the seats in order
+    fn from_deal(ruleset: Ruleset, teams: Vec<i8>, deal: Deal) -> Self {
+        assert_eq!(deal.stacks.len(), teams.len(), "a STACK is dealt for every seat");
+        let players = deal.stacks.into_iter().map(|stack| PlayerState::new(stack, ruleset.side_piles)).collect();
+
+        SkipBoGame {
+            ruleset,
+            playing_field: vec![BuildingPile::new(); ruleset.building_piles], 
+            completed_cards: Vec::new(),
+            draw_pile: deal.draw_pile,
+            players,
+            teams,
+            turn: 0,
+            moves_played: 0,
+            idle_turns: 0,
+            end: false,
+            winner: -1,
+            history: Vec::new(),
+            undone: Vec::new(),
+            seed: deal.seed,
+            rng: deal.rng
+        }
+    }
+
+    // Rebuild a game from what a player sees and a guess of the cards he cannot see, to simulate how it could go on.
+    // The history starts empty and nobody has forfeited
+    fn from_view(view: &GameView, hidden: HiddenCards, seed: u64) -> Self {
+        let player_count = view.opponents.len() + 1;
+        let mut players: Vec<PlayerState> = vec![PlayerState::new(Vec::new(), view.ruleset.side_piles); player_count];
+        let mut teams: Vec<i8> = vec![view.team; player_count];
+
+        for (index, (stack, hand)) in hidden.stacks.into_iter().zip(hidden.hands).enumerate() {
+            players[index].stack = stack;
+            players[index].hand = hand;
+        }
+
+        let me = &mut players[view.player_num as usize];
+        me.stack.extend(view.stack_top);
+        me.hand = view.hand.to_vec();
+        me.side = view.side.to_vec();
+        for o in &view.opponents {
+            let p = &mut players[o.player_num as usize];
+            p.stack.extend(o.stack_top);
+            p.side = o.side.to_vec();
+            teams[o.player_num as usize] = o.team;
+        }
+
         SkipBoGame {
-            playing_field: [(12, false), (12, false), (12, false), (12, false)], 
+            ruleset: *view.ruleset,
+            playing_field: view.playing_field.to_vec(),
+            completed_cards: view.completed_cards.to_vec(),
+            draw_pile: hidden.draw_pile,
             players,
+            teams,
+            turn: view.turn,
+            moves_played: 0,
+            idle_turns: 0,
             end: false,
             winner: -1,
-            rng: rand::thread_rng()
+            history: Vec::new(),
+            undone: Vec::new(),
+            seed,
+            rng: StdRng::seed_from_u64(seed)
         }
     }
 
     // Modify gamestate to move card from STACK to FIELD
     fn to_playing_field_from_stack(&mut self, player_num: i8, stack: i8) {
-        let card = self.players[player_num as usize].stack.last().expect("stack is not empty");
+        let card = *self.players[player_num as usize].stack.last().expect("stack is not empty");
 
-        if card == &-1 {
-            // if the card is a joker, set the "joker-flag" to true
-            if self.playing_field[stack as usize].0 == 12 {
-                self.playing_field[stack as usize] = (1, true);
-            } else {
-                self.playing_field[stack as usize].0 += 1;
-                self.playing_field[stack as usize].1 = true;
-            }
-        } else {
-            self.playing_field[stack as usize] = (*card, false);
-        }
+        self.playing_field[stack as usize].push(card, self.ruleset.highest_card);
 
         // Remove card from player's stack
         self.players[player_num as usize].stack.pop().expect("Stack not emtpy");
@@ -61,18 +279,8 @@ impl Game for SkipBoGame {
     // Modify gamestate to move card from HAND to FIELD
     fn to_playing_field_from_hand(&mut self, player_num: i8, stack_field: i8, stack_hand: i8) {
         let card = self.players[player_num as usize].hand[stack_hand as usize];
-        
-        // if the card is a joker, set the "joker-flag" to true
-        if card == -1 {
-            if self.playing_field[stack_field as usize].0 == 12 {
-                self.playing_field[stack_field as usize] = (1, true);
-            } else {
-                self.playing_field[stack_field as usize].0 += 1;
-                self.playing_field[stack_field as usize].1 = true;
-            }
-        } else {
-            self.playing_field[stack_field as usize] = (card, false);
-        }
+
+        self.playing_field[stack_field as usize].push(card, self.ruleset.highest_card);
 
         // Remove card from player's hand
         self.players[player_num as usize].hand.remove(stack_hand as usize);
@@ -81,18 +289,8 @@ impl Game for SkipBoGame {
     // Modify gamestate to move card from SIDE to FIELD
     fn to_playing_field_from_side(&mut self, player_num: i8, stack_field: i8, stack_side: i8) {
         let card = self.players[player_num as usize].side[stack_side as usize].last().copied().expect("Stack contains at least one card and it is copyable.");
-        
-        // if the card is a joker, set the "joker-flag" to true
-        if card == -1 {
-            if self.playing_field[stack_field as usize].0 == 12 {
-                self.playing_field[stack_field as usize] = (1, true);
-            } else {
-                self.playing_field[stack_field as usize].0 += 1;
-                self.playing_field[stack_field as usize].1 = true;
-            }
-        } else {
-            self.playing_field[stack_field as usize] = (card, false);
-        }
+
+        self.playing_field[stack_field as usize].push(card, self.ruleset.highest_card);
 
         // Remove card from player's side
         self.players[player_num as usize].side[stack_side as usize].pop();
@@ -105,68 +303,260 @@ impl Game for SkipBoGame {
         self.players[player_num as usize].hand.remove(stack_hand as usize);
     }
 
-    // Take a Move-Object and translate it to the corresponding functions
-    fn execute_move(&mut self, player_num: i8, m: &Move) {
-        if m.from == CardStack::Stack {
-            self.to_playing_field_from_stack(player_num, m.to_num);
+    // Remove a FIELD stack that reached the highest card and set its cards aside, so that the stack starts again at 1
+    fn clear_completed_pile(&mut self, stack_field: i8) {
+        // With wrap-around the stack simply continues at 1 and is never cleared
+        if self.ruleset.wrap_around {
+            return;
         }
 
-        if m.from == CardStack::Hand {
-            if m.to == CardStack::Field {
-                self.to_playing_field_from_hand(player_num, m.to_num, m.from_num);
-            } else if m.to == CardStack::Side {
-                self.to_side(player_num, m.from_num, m.to_num);
+        if self.playing_field[stack_field as usize].is_complete(self.ruleset.highest_card) {
+            let cards = self.playing_field[stack_field as usize].cards.clone();
+            self.record(GameEvent::PileCompleted { pile: stack_field, cards });
+        }
+    }
+
+    // Check that a Move follows the rules before it is executed
+    fn validate_move(&self, player_num: i8, m: &Move) -> Result<(), MoveError> {
+        let p = &self.players[player_num as usize];
+
+        // Find the card that is moved
+        let partner = |partner: i8| if self.is_partner(player_num, partner) { Ok(&self.players[partner as usize]) } else { Err(MoveError::NotPartner(partner)) };
+        let card = match m.from {
+            CardStack::Stack => p.stack.last().copied().ok_or(MoveError::EmptySource(CardStack::Stack))?,
+            CardStack::Hand => {
+                if p.hand.is_empty() {
+                    return Err(MoveError::EmptySource(CardStack::Hand));
+                }
+                *p.hand.get(m.from_num as usize).filter(|_| m.from_num >= 0).ok_or(MoveError::IndexOutOfRange { stack: CardStack::Hand, index: m.from_num })?
             }
+            CardStack::Side => {
+                let side = p.side.get(m.from_num as usize).filter(|_| m.from_num >= 0).ok_or(MoveError::IndexOutOfRange { stack: CardStack::Side, index: m.from_num })?;
+                side.last().copied().ok_or(MoveError::EmptySource(CardStack::Side))?
+            }
+            CardStack::PartnerStack(q) => partner(q)?.stack.last().copied().ok_or(MoveError::EmptySource(m.from))?,
+            CardStack::PartnerSide(q) => {
+                let side = partner(q)?.side.get(m.from_num as usize).filter(|_| m.from_num >= 0).ok_or(MoveError::IndexOutOfRange { stack: m.from, index: m.from_num })?;
+                side.last().copied().ok_or(MoveError::EmptySource(m.from))?
+            }
+            CardStack::Field => return Err(MoveError::IllegalRoute { from: m.from, to: m.to })
+        };
+
+        // Check that the card may be put where the player wants it
+        match m.to {
+            CardStack::Field => {
+                let pile = self.playing_field.get(m.to_num as usize).filter(|_| m.to_num >= 0).ok_or(MoveError::IndexOutOfRange { stack: CardStack::Field, index: m.to_num })?;
+                if !pile.accepts(card, &self.ruleset) {
+                    return Err(MoveError::IllegalCardForPile { card, pile: m.to_num });
+                }
+            }
+            CardStack::Side if m.from == CardStack::Hand => {
+                if m.to_num < 0 || m.to_num as usize >= p.side.len() {
+                    return Err(MoveError::IndexOutOfRange { stack: CardStack::Side, index: m.to_num });
+                }
+            }
+            _ => return Err(MoveError::IllegalRoute { from: m.from, to: m.to })
         }
 
-        if m.from == CardStack::Side {
-            self.to_playing_field_from_side(player_num, m.to_num, m.from_num)
+        Ok(())
+    }
+
+    // Take a Move-Object, check it and translate it to the corresponding functions
+    fn execute_move(&mut self, player_num: i8, m: &Move) -> Result<(), MoveError> {
+        self.validate_move(player_num, m)?;
+
+        let p = &self.players[player_num as usize];
+        let card = match m.from {
+            CardStack::Stack => *p.stack.last().unwrap(),
+            CardStack::Hand => p.hand[m.from_num as usize],
+            CardStack::PartnerStack(q) => *self.players[q as usize].stack.last().unwrap(),
+            CardStack::PartnerSide(q) => *self.players[q as usize].side[m.from_num as usize].last().unwrap(),
+            _ => *p.side[m.from_num as usize].last().unwrap()
+        };
+        self.record(GameEvent::MovePlayed { player_num, m: *m, card });
+
+        if m.to == CardStack::Field {
+            self.clear_completed_pile(m.to_num);
+        }
+
+        Ok(())
+    }
+
+    // Execute the Move that ends a player's turn, which has to put a card from HAND on SIDE
+    fn discard(&mut self, player_num: i8, m: &Move) -> Result<(), MoveError> {
+        if m.to == CardStack::Field {
+            return Err(MoveError::DiscardToField);
         }
+
+        self.execute_move(player_num, m)
     }
 
-    // Refill the player's hand with cards to five (called before every turn and if the player plays all cards from HAND during the turn)
+    // A player that tries to break the rules is out of the game
+    fn forfeit(&mut self, player_num: i8, m: &Move, error: MoveError) {
+        self.record(GameEvent::Forfeited { player_num, m: *m, error });
+    }
+
+    // Draw the top card from the draw pile into the player's HAND. If it is empty, the cards of the completed FIELD stacks are shuffled back into it first
+    fn draw_card(&mut self, player_num: i8) -> Option<Card> {
+        if self.draw_pile.is_empty() {
+            // FIELD stacks are never completed with wrap-around, so every card below the visible ones is reused instead
+            let drained: Vec<Vec<Card>> = self.playing_field.iter().map(|pile| {
+                let covered = if self.ruleset.wrap_around { pile.cards.len().saturating_sub(1) } else { 0 };
+                pile.cards[..covered].to_vec()
+            }).collect();
+
+            let mut draw_pile: Vec<Card> = self.completed_cards.iter().chain(drained.iter().flatten()).copied().collect();
+            draw_pile.shuffle(&mut self.rng);
+
+            if !draw_pile.is_empty() {
+                self.record(GameEvent::Reshuffled { drained, completed: self.completed_cards.clone(), draw_pile });
+            }
+        }
+
+        let card = *self.draw_pile.last()?;
+        self.record(GameEvent::CardDrawn { player_num, card });
+
+        Some(card)
+    }
+
+    // Refill the player's hand to the hand size (called before every turn and if the player plays all cards from HAND during the turn)
     fn refill_hand(&mut self, player_num: i8) {
-        let length = self.players[player_num as usize].hand.len();
+        while self.players[player_num as usize].hand.len() < self.ruleset.hand_size {
+            if self.draw_card(player_num).is_none() {
+                break;
+            }
+        }
+    }
+
+    // Count the finished turn and keep track of turns in which no card was moved
+    fn end_turn(&mut self, player_num: i8, idle: bool) {
+        self.record(GameEvent::TurnEnded { player_num, idle, idle_turns: self.idle_turns });
+    }
 
-        if length < 5 {
-            let mut new_cards: Vec<i8> = Vec::new();
-            for _ in 0..(5 - length) {
-                let mut random_num = self.rng.gen_range(1..14);
-                if random_num == 13 { random_num = -1 };
-                new_cards.push(random_num);
-            }  
-            self.players[player_num as usize].hand.append(&mut new_cards);
+    // Take back the last move together with everything that happened after it (drawn cards, completed FIELD stacks, ended turns)
+    fn undo(&mut self) -> bool {
+        if !self.history.iter().any(|e| e.is_move()) {
+            return false;
         }
+
+        let mut group: Vec<GameEvent> = Vec::new();
+        while let Some(event) = self.history.pop() {
+            self.revert_event(&event);
+            let is_move = event.is_move();
+            group.insert(0, event);
+
+            if is_move {
+                break;
+            }
+        }
+        self.undone.push(group);
+
+        // The game could have been won by the move that was taken back
+        self.end = false;
+        self.winner = -1;
+
+        true
     }
 
-    // Check for win by checking if any player's stack is empty
-    fn check_win(&mut self) -> bool {
-        for (index, player) in self.players.iter().enumerate() {
-            if player.stack.is_empty() {
-                self.end = true;
-                self.winner = index as i8;
-                return true
+    // Play the last move that was taken back again
+    fn redo(&mut self) -> bool {
+        match self.undone.pop() {
+            None => false,
+            Some(group) => {
+                for event in group {
+                    self.apply_event(&event);
+                    self.history.push(event);
+                }
+                true
             }
         }
+    }
+
+    // Check for win by checking if any player's stack is empty. In team play the STACKs of all partners have to be empty, the winner is the team's first player
+    fn check_win(&mut self) -> bool {
+        let team_stack = |index: usize| -> usize {
+            self.players.iter().zip(&self.teams).filter(|(_, t)| **t == self.teams[index]).map(|(p, _)| p.stack.len()).sum()
+        };
+
+        if let Some(index) = (0..self.players.len()).find(|i| team_stack(*i) == 0) {
+            self.end = true;
+            self.winner = index as i8;
+            return true
+        }
+
+        // If all other teams forfeited, the first player left wins
+        let active: Vec<usize> = (0..self.players.len()).filter(|i| self.players[*i].forfeited.is_none()).collect();
+        if !active.is_empty() && active.iter().all(|i| self.teams[*i] == self.teams[active[0]]) && self.teams.iter().any(|t| *t != self.teams[active[0]]) {
+            self.end = true;
+            self.winner = active[0] as i8;
+            return true
+        }
+
+        // The game is stuck once every player passed his turn without moving a card (the draw pile is exhausted) and no player
+        // left in the game has a card he could play onto FIELD. Players that keep passing although they could play would go on
+        // forever, so the game is called off after MAX_TURNS. Both end in a draw
+        let stuck = self.idle_turns >= self.players.len() as i32 && active.iter().all(|i| self.get_valid_moves(*i as i8).is_empty());
+        if stuck || self.turn >= MAX_TURNS {
+            self.end = true;
+            self.winner = -1;
+            return true
+        }
+
         false
     }
 
+    // Let the players take turns in seat order until the game is won or drawn and return the number of rounds played. The
+    // players after the winner do not play their turn anymore
+    fn play_out(&mut self, players: &[Box<dyn Player>]) -> i32 {
+        let mut rounds = 0;
+        while !self.check_win() {
+            for (index, player) in players.iter().enumerate() {
+                if self.check_win() {
+                    break;
+                }
+                self.play(index as i8, player.as_ref());
+            }
+            rounds += 1;
+        }
+        rounds
+    }
+
+    // Players on the same team can play from each other's STACK and SIDE
+    fn is_partner(&self, player_num: i8, other: i8) -> bool {
+        player_num != other && self.teams.get(other as usize) == Some(&self.teams[player_num as usize])
+    }
+
     // Return Vec with every move that a player could make. The player then selects on of these to execute
-    fn get_valid_moves(&self, playing_field: [(i8, bool); 4], hand: Vec<i8>, side: [Vec<i8>; 4], stack: Vec<i8>) -> Vec<Move> {
+    fn get_valid_moves(&self, player_num: i8) -> Vec<Move> {
         let mut valid_moves = Vec::<Move>::new();
+        let p = &self.players[player_num as usize];
+        let (playing_field, hand, side) = (&self.playing_field, &p.hand, &p.side);
+        let partners: Vec<i8> = (0..self.players.len() as i8).filter(|q| self.is_partner(player_num, *q)).collect();
 
-        // Check if the player can place any card from the STACK
+        // Check if the player can place any card from the STACK. In team play his own STACK can be empty while his partners' are not
         for (index, f) in playing_field.iter().enumerate() {
-            let l = stack.last().expect("empty stack");
-            if l == &(f.0 + 1) || (f.0 == 12 && l == &1) || (l == &-1 && !f.1) {
-                valid_moves.push(Move { from: CardStack::Stack, from_num: 0, to: CardStack::Field, to_num: index as i8 })
+            if let Some(l) = p.stack.last() {
+                if f.accepts(*l, &self.ruleset) {
+                    valid_moves.push(Move { from: CardStack::Stack, from_num: 0, to: CardStack::Field, to_num: index as i8 })
+                }
+            }
+        }
+
+        // Check if the player can place any card from his partners' STACK
+        for q in &partners {
+            for (index, f) in playing_field.iter().enumerate() {
+                if let Some(l) = self.players[*q as usize].stack.last() {
+                    if f.accepts(*l, &self.ruleset) {
+                        valid_moves.push(Move { from: CardStack::PartnerStack(*q), from_num: 0, to: CardStack::Field, to_num: index as i8 })
+                    }
+                }
             }
         }
 
         // Check if the player can place any card from the HAND
         for (index_f, f) in playing_field.iter().enumerate() {
             for (index_h, h) in hand.iter().enumerate() {
-                if f.0 + 1 == *h || (h == &-1 && !f.1) || (f.0 == 12 && h == &1) {
+                if f.accepts(*h, &self.ruleset) {
                     valid_moves.push(Move { from: CardStack::Hand, from_num: index_h as i8, to: CardStack::Field, to_num: index_f as i8 })
                 }
             }
@@ -179,45 +569,111 @@ impl Game for SkipBoGame {
                     continue;
                 }
                 let l = s.last().expect("empty stack");
-                if f.0 +1 == *l || (l == &-1 && !f.1) || (f.0 == 12 && l == &1) {
+                if f.accepts(*l, &self.ruleset) {
                     valid_moves.push(Move { from: CardStack::Side, from_num: index_s as i8, to: CardStack::Field, to_num: index_f as i8 })
                 }
             }
         }
 
+        // Check if the player can place any card from his partners' SIDE
+        for q in &partners {
+            for (index_s, s) in self.players[*q as usize].side.iter().enumerate() {
+                for (index_f, f) in playing_field.iter().enumerate() {
+                    if let Some(l) = s.last() {
+                        if f.accepts(*l, &self.ruleset) {
+                            valid_moves.push(Move { from: CardStack::PartnerSide(*q), from_num: index_s as i8, to: CardStack::Field, to_num: index_f as i8 })
+                        }
+                    }
+                }
+            }
+        }
+
         valid_moves
     }
 
+    // Collect what the player is allowed to see of the game
+    fn view(&self, player_num: i8) -> GameView<'_> {
+        let p = &self.players[player_num as usize];
+        let player_count = self.players.len();
+
+        // List the opponents in turn order, starting with the next player
+        let opponents = (1..player_count).map(|offset| {
+            let index = (player_num as usize + offset) % player_count;
+            let o = &self.players[index];
+            OpponentView { player_num: index as i8, team: self.teams[index], stack_top: o.stack.last().copied(), stack_size: o.stack.len(), hand_size: o.hand.len(), side: &o.side }
+        }).collect();
+
+        GameView {
+            player_num,
+            team: self.teams[player_num as usize],
+            hand: &p.hand,
+            side: &p.side,
+            stack_top: p.stack.last().copied(),
+            stack_size: p.stack.len(),
+            opponents,
+            playing_field: &self.playing_field,
+            completed_cards: &self.completed_cards,
+            draw_pile_size: self.draw_pile.len(),
+            turn: self.turn,
+            ruleset: &self.ruleset
+        }
+    }
+
     // Let the player play a card, if he is done he returns false
-    fn play_move(&mut self, player_num: i8, player: &Box<dyn Player>) -> bool {
-        let playing_field = self.playing_field;
-        let p = &self.players[player_num as usize].clone();
+    fn play_move(&mut self, player_num: i8, player: &dyn Player) -> bool {
+        let p = &self.players[player_num as usize];
+
+        // A player that forfeited is out of the game and moves no more cards
+        if p.forfeited.is_some() {
+            return false;
+        }
 
         // Get all valid moves the player could make
-        let valid_moves = self.get_valid_moves(playing_field, p.hand.clone(), p.side.clone(), p.stack.clone());
+        let valid_moves = self.get_valid_moves(player_num);
+
+        // If every card is in play and the player's HAND is empty, he has nothing to put on SIDE and ends his turn
+        if valid_moves.is_empty() && p.hand.is_empty() {
+            return false;
+        }
 
         // If the player cannot play anything to FIELD anymore, he has to play one card to SIDE
         if valid_moves.is_empty() {
             // Let player choose which card to play from HAND to SIDE and execute it
-            let move_to_side = &player.select_stack(p.hand.clone(), p.side.clone());
-            self.execute_move(player_num, move_to_side);
+            let move_to_side = player.select_stack(&self.view(player_num));
+            if let Err(error) = self.discard(player_num, &move_to_side) {
+                self.forfeit(player_num, &move_to_side, error);
+            }
 
             // Return false to end move
             false
         } else {
             // Let player select move to play
-            let opponent_stack = self.players[((player_num + 1) % (self.players.len() as i8)) as usize].stack.last().unwrap();
-            let selected_move = player.select_move(valid_moves, *p.stack.last().unwrap(), *opponent_stack, p.side.clone(), p.hand.clone(), playing_field);
+            let selected_move = player.select_move(&valid_moves, &self.view(player_num));
 
             match selected_move {
                 None => {
+                    if self.players[player_num as usize].hand.is_empty() {
+                        return false
+                    }
+
                     // Put card to side if player executes no move and end turn
-                    self.execute_move(player_num, &player.select_stack(p.hand.clone(), p.side.clone()));
+                    let move_to_side = player.select_stack(&self.view(player_num));
+                    if let Err(error) = self.discard(player_num, &move_to_side) {
+                        self.forfeit(player_num, &move_to_side, error);
+                    }
                     return false
                 }
                 Some(m) => {
-                    // Execute the move the player chose
-                    self.execute_move(player_num, &m);
+                    // Execute the move the player chose, an illegal move costs the player the game
+                    if let Err(error) = self.execute_move(player_num, &m) {
+                        self.forfeit(player_num, &m, error);
+                        return false
+                    }
+
+                    // Putting a card on SIDE ends the turn
+                    if m.to == CardStack::Side {
+                        return false
+                    }
                 }
             }
 
@@ -227,10 +683,23 @@ impl Game for SkipBoGame {
     }
 
     // Let player play an entire turn
-    fn play(&mut self, player_num: i8, player: &Box<dyn Player>) {
+    fn play(&mut self, player_num: i8, player: &dyn Player) {
+        // A player that forfeited does not play anymore
+        if self.players[player_num as usize].forfeited.is_some() {
+            self.end_turn(player_num, true);
+            return;
+        }
+
         // Refill HAND at the beginning
         self.refill_hand(player_num);
 
+        let moves_before = self.moves_played;
+        self.continue_turn(player_num, player);
+        self.end_turn(player_num, self.moves_played == moves_before);
+    }
+
+    // Let the player play moves until his turn is over, without refilling his HAND first or ending the turn
+    fn continue_turn(&mut self, player_num: i8, player: &dyn Player) {
         let mut state = true;
         while state {
             // Check for win before letting the player play to avoid exceptions because of an empty STACK
@@ -247,4 +716,175 @@ impl Game for SkipBoGame {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::players::{good_player::GoodPlayer, simple_player::SimplePlayer};
+
+    type State = (Vec<BuildingPile>, Vec<Card>, Vec<Card>, Vec<PlayerState>, i32, i32, i32);
+
+    fn state(game: &SkipBoGame) -> State {
+        (game.playing_field.clone(), game.completed_cards.clone(), game.draw_pile.clone(), game.players.clone(), game.turn, game.moves_played, game.idle_turns)
+    }
+
+    fn players() -> Vec<Box<dyn Player>> {
+        vec![Box::new(GoodPlayer::default()), Box::new(SimplePlayer {})]
+    }
+
+    #[test]
+    fn undo_restores_the_state_before_the_move() {
+        let mut game = SkipBoGame::with_seed(Ruleset::official(), 2, 1);
+        for _ in 0..10 {
+            for (index, player) in players().iter().enumerate() {
+                game.play(index as i8, player.as_ref());
+            }
+        }
+        game.refill_hand(0);
+        let before = state(&game);
+
+        let m = game.get_valid_moves(0)[0];
+        game.execute_move(0, &m).unwrap();
+        let after = state(&game);
+
+        assert!(game.undo());
+        assert_eq!(state(&game), before);
+        assert!(game.redo());
+        assert_eq!(state(&game), after);
+        assert!(!game.redo());
+
+        // A new move drops the move that was taken back
+        assert!(game.undo());
+        game.execute_move(0, &m).unwrap();
+        assert_eq!(state(&game), after);
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn undo_and_redo_a_whole_game() {
+        let mut reshuffled = false;
+        for ruleset in [Ruleset::official(), Ruleset::house_wrap_around()] {
+            for seed in 0..5 {
+                let mut game = SkipBoGame::with_seed(ruleset, 2, seed);
+                game.play_out(&players());
+                let (end, winner) = (state(&game), game.winner);
+                reshuffled |= game.history.iter().any(|e| matches!(e, GameEvent::Reshuffled { .. }));
+
+                // Everything up to the first move is the first player's HAND being dealt
+                let mut start = SkipBoGame::with_seed(ruleset, 2, seed);
+                start.refill_hand(0);
+
+                while game.undo() {}
+                assert_eq!(state(&game), state(&start));
+                assert_eq!(game.history, start.history);
+                assert_eq!(game.winner, -1);
+
+                while game.redo() {}
+                assert_eq!(state(&game), end);
+                game.check_win();
+                assert_eq!(game.winner, winner);
+            }
+        }
+        assert!(reshuffled, "no game ran out of cards to draw");
+    }
+
+    // Returns a move that breaks the rules whenever it is asked
+    struct Cheater;
+
+    impl Player for Cheater {
+        fn name(&self) -> String {
+            "cheater".to_string()
+        }
+
+        fn select_move(&self, _: &[Move], _: &GameView) -> Option<Move> {
+            Some(Move { from: CardStack::Hand, from_num: 9, to: CardStack::Field, to_num: 0 })
+        }
+
+        fn select_stack(&self, _: &GameView) -> Move {
+            Move { from: CardStack::Hand, from_num: 9, to: CardStack::Side, to_num: 0 }
+        }
+    }
+
+    #[test]
+    fn validate_move_names_the_broken_rule() {
+        let mut game = SkipBoGame::with_seed(Ruleset::official(), 2, 3);
+        game.players[0].hand = vec![Card::Number(5), Card::Number(1)];
+        game.players[0].stack.push(Card::Number(7));
+        let to_field = |from: CardStack, from_num: i8, to_num: i8| Move { from, from_num, to: CardStack::Field, to_num };
+
+        assert_eq!(game.validate_move(0, &to_field(CardStack::Hand, 2, 0)), Err(MoveError::IndexOutOfRange { stack: CardStack::Hand, index: 2 }));
+        assert_eq!(game.validate_move(0, &to_field(CardStack::Hand, 1, 4)), Err(MoveError::IndexOutOfRange { stack: CardStack::Field, index: 4 }));
+        assert_eq!(game.validate_move(0, &to_field(CardStack::Side, 0, 0)), Err(MoveError::EmptySource(CardStack::Side)));
+        assert_eq!(game.validate_move(0, &to_field(CardStack::Hand, 0, 0)), Err(MoveError::IllegalCardForPile { card: Card::Number(5), pile: 0 }));
+        assert_eq!(game.validate_move(0, &to_field(CardStack::Stack, 0, 2)), Err(MoveError::IllegalCardForPile { card: Card::Number(7), pile: 2 }));
+        let stack_to_side = Move { from: CardStack::Stack, from_num: 0, to: CardStack::Side, to_num: 0 };
+        assert_eq!(game.validate_move(0, &stack_to_side), Err(MoveError::IllegalRoute { from: CardStack::Stack, to: CardStack::Side }));
+        assert_eq!(game.validate_move(0, &to_field(CardStack::PartnerStack(1), 0, 0)), Err(MoveError::NotPartner(1)));
+        assert_eq!(game.discard(0, &to_field(CardStack::Hand, 1, 0)), Err(MoveError::DiscardToField));
+
+        assert_eq!(game.validate_move(0, &to_field(CardStack::Hand, 1, 0)), Ok(()));
+        game.players[0].hand.clear();
+        assert_eq!(game.validate_move(0, &to_field(CardStack::Hand, 0, 0)), Err(MoveError::EmptySource(CardStack::Hand)));
+    }
+
+    #[test]
+    fn an_illegal_move_forfeits_the_game() {
+        let players: Vec<Box<dyn Player>> = vec![Box::new(Cheater), Box::new(SimplePlayer {})];
+        let mut game = SkipBoGame::with_seed(Ruleset::official(), 2, 8);
+        game.play_out(&players);
+
+        assert!(matches!(game.players[0].forfeited, Some(MoveError::IndexOutOfRange { stack: CardStack::Hand, index: 9 })));
+        assert!(game.players[1].forfeited.is_none());
+        assert_eq!(game.winner, 1);
+        assert!(game.history.iter().any(|e| matches!(e, GameEvent::Forfeited { player_num: 0, .. })));
+    }
+
+    #[test]
+    fn a_team_wins_once_all_its_stacks_are_empty() {
+        let mut game = SkipBoGame::with_teams(Ruleset::official(), vec![0, 1, 0, 1], 4);
+        game.players[0].stack.clear();
+        assert!(!game.check_win());
+        assert_eq!(game.winner, -1);
+
+        game.players[2].stack.clear();
+        assert!(game.check_win());
+        assert_eq!(game.teams[game.winner as usize], 0);
+    }
+
+    #[test]
+    fn only_partners_share_their_cards() {
+        let mut game = SkipBoGame::with_teams(Ruleset::official(), vec![0, 1, 0, 1], 6);
+        for p in 1..4 {
+            game.players[p].stack.push(Card::Number(1));
+            game.players[p].side[0].push(Card::Number(1));
+        }
+        let from = |from: CardStack| Move { from, from_num: 0, to: CardStack::Field, to_num: 0 };
+
+        assert_eq!(game.validate_move(0, &from(CardStack::PartnerStack(2))), Ok(()));
+        assert_eq!(game.validate_move(0, &from(CardStack::PartnerSide(2))), Ok(()));
+        for q in [1, 3] {
+            assert_eq!(game.validate_move(0, &from(CardStack::PartnerStack(q))), Err(MoveError::NotPartner(q)));
+            assert_eq!(game.validate_move(0, &from(CardStack::PartnerSide(q))), Err(MoveError::NotPartner(q)));
+        }
+        // A seat is no partner of itself
+        assert_eq!(game.validate_move(0, &from(CardStack::PartnerStack(0))), Err(MoveError::NotPartner(0)));
+
+        let moves = game.get_valid_moves(0);
+        assert!(moves.contains(&from(CardStack::PartnerStack(2))));
+        assert!(moves.iter().all(|m| !matches!(m.from, CardStack::PartnerStack(q) | CardStack::PartnerSide(q) if q != 2)));
+    }
+
+    #[test]
+    fn a_forfeited_seat_cannot_move() {
+        let mut game = SkipBoGame::with_teams(Ruleset::official(), vec![0, 1, 0, 1], 2);
+        game.refill_hand(0);
+        game.players[0].stack.push(Card::Wild);
+        let m = Move { from: CardStack::Hand, from_num: 0, to: CardStack::Side, to_num: 9 };
+        game.forfeit(0, &m, MoveError::IndexOutOfRange { stack: CardStack::Side, index: 9 });
+        assert!(!game.get_valid_moves(0).is_empty());
+
+        let before = state(&game);
+        assert!(!game.play_move(0, &GoodPlayer::default()));
+        assert_eq!(state(&game), before);
+    }
+}
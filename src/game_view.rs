@@ -0,0 +1,45 @@
+use crate::{card::Card, building_pile::BuildingPile, ruleset::Ruleset};
+
+// What a player can see of another player: his SIDE and the visible card of his STACK
+#[derive(Debug, Clone)]
+pub struct OpponentView<'a> {
+    pub player_num: i8,
+    pub team: i8,
+    pub stack_top: Option<Card>,
+    pub stack_size: usize,
+    // Number of cards in his HAND, the cards themselves are hidden
+    pub hand_size: usize,
+    pub side: &'a [Vec<Card>]
+}
+
+// Everything a player can see of the game when it is his turn, handed to the Player to select his moves
+#[derive(Debug, Clone)]
+pub struct GameView<'a> {
+    pub player_num: i8,
+    // Players with the same team number are partners, without team play every player is his own team
+    pub team: i8,
+    pub hand: &'a [Card],
+    pub side: &'a [Vec<Card>],
+    pub stack_top: Option<Card>,
+    pub stack_size: usize,
+    // Every other player in turn order, starting with the next player. In team play this includes the partners
+    pub opponents: Vec<OpponentView<'a>>,
+    pub playing_field: &'a [BuildingPile],
+    // Cards of completed FIELD stacks that are shuffled back into the draw pile once it runs out
+    pub completed_cards: &'a [Card],
+    pub draw_pile_size: usize,
+    pub turn: i32,
+    pub ruleset: &'a Ruleset
+}
+
+impl<'a> GameView<'a> {
+    // The first player after this player's turn who is not on his team
+    pub fn next_opponent(&self) -> Option<&OpponentView<'a>> {
+        self.opponents.iter().find(|o| o.team != self.team)
+    }
+
+    // The other players on this player's team
+    pub fn partners(&self) -> impl Iterator<Item = &OpponentView<'a>> {
+        self.opponents.iter().filter(move |o| o.team == self.team)
+    }
+}
@@ -0,0 +1,25 @@
+use crate::{move_stack::{Move, MoveError}, card::Card};
+
+// Everything that changes the state of a game. Every event carries what is needed to apply it again and to revert it
+#[derive(Debug, PartialEq, Clone)]
+pub enum GameEvent {
+    // A card was moved by a player
+    MovePlayed { player_num: i8, m: Move, card: Card },
+    // A player drew a card from the draw pile into his HAND
+    CardDrawn { player_num: i8, card: Card },
+    // A FIELD stack reached the highest card and its cards were set aside
+    PileCompleted { pile: i8, cards: Vec<Card> },
+    // The draw pile ran out and was refilled: the cards drained from the bottom of every FIELD stack (only with wrap-around),
+    // the completed cards before the refill and the new draw pile
+    Reshuffled { drained: Vec<Vec<Card>>, completed: Vec<Card>, draw_pile: Vec<Card> },
+    // A player was disqualified for trying an illegal move
+    Forfeited { player_num: i8, m: Move, error: MoveError },
+    // A player ended his turn, idle if he did not move any card. Stores the idle turn counter from before
+    TurnEnded { player_num: i8, idle: bool, idle_turns: i32 }
+}
+
+impl GameEvent {
+    pub fn is_move(&self) -> bool {
+        matches!(self, GameEvent::MovePlayed { .. })
+    }
+}
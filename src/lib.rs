@@ -0,0 +1,40 @@
+// Skip-Bo game engine: the rules, the game state, players and game records.
+// The simulator in main.rs is one consumer of this library, other crates can depend on it to write their own players and tools
+pub mod game;
+pub mod players;
+pub mod move_stack;
+pub mod deck;
+pub mod ruleset;
+pub mod card;
+pub mod building_pile;
+pub mod game_view;
+pub mod history;
+pub mod record;
+pub mod determinization;
+pub mod evaluation;
+pub mod tuning;
+pub mod runner;
+pub mod stats;
+pub mod sprt;
+pub mod tournament;
+pub mod duplicate;
+
+pub use game::{Game, SkipBoGame, MAX_TURNS};
+pub use deck::Deal;
+pub use players::player::{Player, PlayerState, NewPlayerState};
+pub use players::registry::{PlayerRegistry, PlayerSpec, PlayerFactory, RegistryError};
+pub use move_stack::{Move, CardStack, MoveError};
+pub use ruleset::{Ruleset, MIN_PLAYERS, MAX_PLAYERS};
+pub use card::Card;
+pub use building_pile::BuildingPile;
+pub use game_view::{GameView, OpponentView};
+pub use history::GameEvent;
+pub use record::{GameRecord, TurnRecord, RecordError, replay};
+pub use determinization::HiddenCards;
+pub use evaluation::{Evaluator, LinearEvaluator, Features, EvaluatorError};
+pub use tuning::{Tuner, TunedParameter};
+pub use runner::Runner;
+pub use stats::{WinRate, Interval, Alternative, TestResult};
+pub use sprt::{Sprt, Decision, SprtMatch, SprtPoint, SprtResults};
+pub use tournament::{Tournament, Pairing, Rating, TrueSkill, Standings};
+pub use duplicate::{DuplicateMatch, DuplicateResults, DealResult};
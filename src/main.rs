@@ -1,119 +1,1023 @@
-pub mod game;
-pub mod players;
-pub mod move_stack;
-
+use clap::{Parser, Subcommand, Args, ValueEnum};
 use indicatif::{ProgressBar, ProgressStyle};
-use std::{time::Instant, vec, ffi::FromVecWithNulError};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Serialize, Deserialize};
+use std::{collections::BTreeMap, fs, path::Path, process, time::Instant, vec};
+
+use rust_skipbo::{Game, SkipBoGame, Player, Ruleset, GameRecord, PlayerRegistry, PlayerSpec, MIN_PLAYERS, MAX_PLAYERS, replay, Tuner, Runner, Sprt, SprtMatch, SprtPoint, Decision, Tournament, Pairing, DuplicateMatch};
+use rust_skipbo::runner::job_seed;
+use rust_skipbo::tuning::default_parameters;
+use rust_skipbo::stats::{WinRate, Interval, Alternative, binomial_test, sign_test};
+use rust_skipbo::deck::new_deck;
+use rust_skipbo::players::human_player::HumanPlayer;
+
+#[derive(Parser)]
+#[command(name = "rust-skipbo", about = "Play and simulate Skip-Bo games between computer players")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+
+    #[arg(help = "How results are printed", long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
 
-use crate::players::{good_player::GoodPlayer, player::NewPlayerState, simple_player::SimplePlayer, player::{Player, PlayerState}};
-use crate::game::{Game, SkipBoGame};
+    #[arg(help = "Threads the games are played on, the results do not depend on it [default: one per core]", long, global = true)]
+    threads: Option<usize>,
+
+    #[arg(help = "Confidence level of the intervals and tests, between 0 and 1", long, global = true, default_value_t = 0.95)]
+    confidence: f64,
+
+    #[arg(help = "Confidence interval of the win rates: wilson or clopper-pearson", long, global = true, default_value_t = Interval::Wilson)]
+    interval: Interval
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Play batches of games between the same players and print the statistics of their winrates")]
+    Simulate(SimulateArgs),
+    #[command(about = "Let the players play each other at tables, rate them with TrueSkill and print a leaderboard and how often each one won against each other")]
+    Tournament(TournamentArgs),
+    #[command(about = "Play every deal once with the players in every seat, so they are compared on the same cards")]
+    Duplicate(DuplicateArgs),
+    #[command(about = "Replay a saved game record and check its result")]
+    Replay {
+        file: String
+    },
+    #[command(about = "Play a game in the terminal against computer players")]
+    Play(PlayArgs),
+    #[command(about = "Play two players against each other until a sequential probability ratio test decides whether the first one is better")]
+    Sprt(SprtArgs),
+    #[command(about = "Tune the parameters of a player with SPSA by playing batches of games and print the best parameters found")]
+    Tune(TuneArgs)
+}
+
+#[derive(Args)]
+struct SimulateArgs {
+    #[command(flatten)]
+    game: GameArgs,
+    #[arg(help = "2 to 6 players in seat order, ex: \"simple,random:seed=7\" [default: simple,good]", long, value_delimiter = ',')]
+    players: Vec<PlayerSpec>,
+    #[arg(help = "Games played per batch, the winrate of a batch is the number of games won", long, default_value_t = 100)]
+    games: usize,
+    #[arg(help = "Number of batches", long, default_value_t = 2000)]
+    batches: usize,
+    #[arg(long, help = "Save the record of every game to this directory as <batch>-<game>.json")]
+    record_dir: Option<String>
+}
+
+#[derive(Args)]
+struct TournamentArgs {
+    #[command(flatten)]
+    game: GameArgs,
+    #[arg(help = "Players taking part, ex: \"simple,good,bad\" [default: simple,good]", long, value_delimiter = ',')]
+    players: Vec<PlayerSpec>,
+    #[arg(help = "Games played by every table in a round, the players move one seat on after every game", long, default_value_t = 1000)]
+    games: usize,
+    #[arg(help = "Players at a table", long, default_value_t = 2)]
+    seats: usize,
+    #[arg(help = "How the tables are drawn: round-robin (every group of players meets once) or swiss:rounds (players of similar rating meet)", long, default_value_t = Pairing::RoundRobin)]
+    pairing: Pairing
+}
+
+#[derive(Args)]
+struct DuplicateArgs {
+    #[command(flatten)]
+    game: GameArgs,
+    #[arg(help = "2 to 6 players in seat order, they move one seat on after every game of a deal [default: simple,good]", long, value_delimiter = ',')]
+    players: Vec<PlayerSpec>,
+    #[arg(help = "Number of deals, every deal is played once per player", long, default_value_t = 1000)]
+    deals: usize,
+    #[arg(long, help = "Save the record of every game to this directory as <deal>-<game>.json")]
+    record_dir: Option<String>
+}
+
+#[derive(Args)]
+struct PlayArgs {
+    #[command(flatten)]
+    game: GameArgs,
+    #[arg(help = "Players sitting after you, ex: \"good\" [default: good]", long, value_delimiter = ',')]
+    opponents: Vec<PlayerSpec>,
+    #[arg(long, help = "Save the game record to this file")]
+    record: Option<String>
+}
+
+#[derive(Args)]
+struct SprtArgs {
+    #[command(flatten)]
+    game: GameArgs,
+    #[arg(help = "The 2 players compared, ex: \"planner,good\" [default: good,simple]", long, value_delimiter = ',')]
+    players: Vec<PlayerSpec>,
+    #[arg(help = "How much more than half of the games the first player has to win, ex: 0.05 tests whether it wins at least 55%", long, default_value_t = 0.05)]
+    margin: f64,
+    #[arg(help = "Chance of deciding that the first player is better although it is not", long, default_value_t = 0.05)]
+    alpha: f64,
+    #[arg(help = "Chance of deciding that the first player is not better although it is", long, default_value_t = 0.05)]
+    beta: f64,
+    #[arg(help = "Games played at most, the test ends without a decision after them", long, default_value_t = 100000)]
+    max_games: usize,
+    #[arg(help = "Games between two reports of the running log-likelihood ratio", long, default_value_t = 100)]
+    report_every: usize
+}
+
+#[derive(Args)]
+struct TuneArgs {
+    #[command(flatten)]
+    game: GameArgs,
+    #[arg(help = "Player whose parameters are tuned, starting from the values it is given, ex: \"planner\" or \"good:block_distance=3\"", long, default_value = "good")]
+    player: PlayerSpec,
+    #[arg(help = "Parameters to tune, ex: \"wilds,side_disorder\" [default: all of the player]", long, value_delimiter = ',')]
+    params: Vec<String>,
+    #[arg(help = "1 to 5 players the tuned player plays against [default: the player before tuning]", long, value_delimiter = ',')]
+    opponents: Vec<PlayerSpec>,
+    #[arg(help = "Number of iterations", long, default_value_t = 100)]
+    iterations: usize,
+    #[arg(help = "Games played by each of the two players compared in an iteration", long, default_value_t = 200)]
+    games: usize,
+    #[arg(help = "Games the tuned and the starting parameters are measured with at the end", long, default_value_t = 2000)]
+    validation: usize,
+    #[arg(help = "Size of the first update, in steps of a parameter per unit of win rate difference", long, default_value_t = 20.0)]
+    learning_rate: f64,
+    #[arg(help = "Size of the first perturbation, in steps of a parameter", long, default_value_t = 1.0)]
+    perturbation: f64,
+    #[arg(long, help = "Save the best parameters and their win rate to this JSON file")]
+    output: Option<String>
+}
+
+#[derive(Args)]
+struct GameArgs {
+    #[arg(help = "Preset ruleset: official, house-wrap-around or quick [default: official]", long)]
+    ruleset: Option<String>,
+    #[arg(long, help = "Number of cards dealt to every STACK, overrides the ruleset. By default 30 cards with up to 4 players and 20 with 5-6 players")]
+    stack_size: Option<usize>,
+    #[arg(long, help = "Seed all games are dealt from, a random one is used if it is missing")]
+    seed: Option<u64>,
+    #[arg(help = "Team of every seat for partnership play, ex: \"0,1,0,1\". Partners play from each other's STACK and SIDE and win together", long, value_delimiter = ',')]
+    teams: Vec<i8>,
+    #[arg(long, help = "JSON file with the players and any of the options above, options on the command line take precedence")]
+    config: Option<String>
+}
+
+// Settings read from a config file, ex: {"players": ["good", "random:seed=7"], "ruleset": "quick"}
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    players: Vec<PlayerSpec>,
+    ruleset: Option<String>,
+    stack_size: Option<usize>,
+    seed: Option<u64>,
+    teams: Vec<i8>
+}
 
-fn play_game(game: &mut SkipBoGame, players: Vec<Box<dyn Player>>) -> (i8, i32) {
-    let mut n = 0;
-    while !game.check_win() {
-        for (index, player) in players.iter().enumerate() {
-            game.play(index as i8, player);
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Text,
+    Json
+}
+
+// What every subcommand is run with besides its own arguments
+struct Context {
+    registry: PlayerRegistry,
+    runner: Runner,
+    confidence: Confidence,
+    format: Format
+}
+
+impl GameArgs {
+    // Fill in the options and players that were not given on the command line from the config file and the defaults
+    fn load(&mut self, players: &mut Vec<PlayerSpec>, default_players: &str) -> Result<(), String> {
+        let config = match &self.config {
+            None => Config::default(),
+            Some(path) => {
+                let json = fs::read_to_string(path).map_err(|e| format!("cannot read config {}: {}", path, e))?;
+                serde_json::from_str(&json).map_err(|e| format!("invalid config {}: {}", path, e))?
+            }
+        };
+
+        self.ruleset = self.ruleset.take().or(config.ruleset);
+        self.stack_size = self.stack_size.or(config.stack_size);
+        self.seed = self.seed.or(config.seed);
+        if self.teams.is_empty() {
+            self.teams = config.teams;
+        }
+        if players.is_empty() {
+            *players = config.players;
+        }
+        if players.is_empty() {
+            *players = default_players.split(',').map(|p| p.parse().expect("default players are valid")).collect();
+        }
+
+        Ok(())
+    }
+
+    // The ruleset for a table of the given size, the STACK size follows the official rules unless it is given
+    fn ruleset(&self, seats: usize) -> Result<Ruleset, String> {
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&seats) {
+            return Err(format!("a game is played by {} to {} players, not {}", MIN_PLAYERS, MAX_PLAYERS, seats));
+        }
+
+        let name = self.ruleset.as_deref().unwrap_or("official");
+        let ruleset = Ruleset::from_name(name).ok_or(format!("unknown ruleset \"{}\"", name))?.for_players(seats);
+        let ruleset = Ruleset { stack_size: self.stack_size.unwrap_or(ruleset.stack_size), ..ruleset };
+
+        let deck = new_deck(&ruleset).len();
+        if ruleset.stack_size * seats > deck {
+            return Err(format!("a deck of {} cards cannot deal {} stacks of {} cards", deck, seats, ruleset.stack_size));
+        }
+        Ok(ruleset)
+    }
+
+    // Without teams every player plays for himself
+    fn teams(&self, seats: usize) -> Result<Vec<i8>, String> {
+        match self.teams.len() {
+            0 => Ok((0..seats as i8).collect()),
+            n if n == seats && self.teams.iter().any(|t| *t != self.teams[0]) => Ok(self.teams.clone()),
+            n if n == seats => Err("team play needs at least 2 teams".to_string()),
+            n => Err(format!("{} team numbers were given for {} players", n, seats))
         }
+    }
 
-        n += 1;
+    fn seed(&self) -> u64 {
+        self.seed.unwrap_or_else(rand::random)
     }
+}
 
-    (game.winner, n)
+// Every player that decides at random is seeded from the RNG, so the games can be repeated with the same seed
+fn build_players(registry: &PlayerRegistry, specs: &[PlayerSpec], rng: &mut StdRng) -> Result<Vec<Box<dyn Player>>, String> {
+    specs.iter().map(|s| registry.build(s, rng.gen()).map_err(|e| e.to_string())).collect()
 }
 
-fn play_n_games(player_number: i64, n: i32) -> Vec<i64> {
-    let mut wins: Vec<i64> = vec![0; player_number as usize];
-    let mut rounds = 0;
-    let rounds_to_play = n;
+// Build the players once to report a bad spec before any game is played
+fn check_players(registry: &PlayerRegistry, specs: &[PlayerSpec]) -> Result<(), String> {
+    build_players(registry, specs, &mut StdRng::seed_from_u64(0)).map(|_| ())
+}
 
-    /* let pb = ProgressBar::new(rounds_to_play as u64);
-    pb.set_style(ProgressStyle::default_bar().template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.green/blue}] {pos:>7}/{len:7} ({eta})")); */
-    for _ in 0..rounds_to_play {
-        let mut game = SkipBoGame::new(vec![PlayerState::new(20), PlayerState::new(20)]);
-        
-        let (w, i) = play_game(&mut game, vec![Box::new(SimplePlayer {}), Box::new(GoodPlayer {})]);
-        
-        wins[w as usize] += 1;
-        rounds += i;
+fn print_json<T: Serialize>(value: &T) {
+    println!("{}", serde_json::to_string_pretty(value).expect("report can be serialized"));
+}
 
-        /* pb.inc(1); */
+// Create the directory the game records of a run are saved to
+fn create_record_dir(dir: &Option<String>) -> Result<(), String> {
+    match dir {
+        None => Ok(()),
+        Some(dir) => fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir, e))
     }
-    /* pb.finish_and_clear(); */
-    //println!("Wins: {:?}, avg round length {}", wins, rounds / rounds_to_play);
+}
+
+// Save the record of a finished game as <name>.json to the directory
+fn save_record(dir: &str, name: &str, game: &SkipBoGame, players: &[PlayerSpec]) -> Result<(), String> {
+    write_record(dir, name, &GameRecord::from_game(game, players.iter().map(|p| p.to_string()).collect()))
+}
 
-    wins
+fn write_record(dir: &str, name: &str, record: &GameRecord) -> Result<(), String> {
+    let path = Path::new(dir).join(format!("{}.json", name));
+    record.save(&path.to_string_lossy()).map_err(|e| format!("{}: {}", path.display(), e))
 }
 
-fn calc_stats(mut winrates: Vec<i64>) -> (f64, i64, i64, f64, i64, f64) {
+// The batches of a simulation, every batch plays a number of games with its own players. With a directory the records of
+// the games are saved to it, named after the batch and the game
+struct Batches<'a> {
+    registry: &'a PlayerRegistry,
+    specs: &'a [PlayerSpec],
+    ruleset: Ruleset,
+    teams: &'a [i8],
+    games: usize,
+    record_dir: Option<&'a str>
+}
+
+impl Batches<'_> {
+    // Every player of the winning team is counted as a winner, a draw counts for nobody
+    fn play(&self, players: &[Box<dyn Player>], rng: &mut StdRng, batch: usize) -> Result<Vec<i64>, String> {
+        let mut wins: Vec<i64> = vec![0; players.len()];
+
+        for g in 0..self.games {
+            let mut game = SkipBoGame::with_teams(self.ruleset, self.teams.to_vec(), rng.gen());
+
+            game.play_out(players);
+            let w = game.winner;
+            if let Some(dir) = self.record_dir {
+                save_record(dir, &format!("{}-{}", batch, g), &game, self.specs)?;
+            }
+            if w < 0 {
+                continue;
+            }
+
+            for (i, t) in self.teams.iter().enumerate() {
+                if *t == self.teams[w as usize] {
+                    wins[i] += 1;
+                }
+            }
+        }
+
+        Ok(wins)
+    }
+
+    // Every batch is played by its own players with its own RNG stream, so the batches can be played on any thread
+    fn stats(&self, batches: usize, seed: u64, runner: &Runner) -> Result<Vec<Stats>, String> {
+        let mut winrates: Vec<Vec<i64>> = vec![Vec::new(); self.specs.len()];
+
+        let pb = progress_bar(batches);
+        let batch_wins = runner.run(batches, seed, |batch, rng| {
+            let players = build_players(self.registry, self.specs, rng).expect("players were built before");
+            self.play(&players, rng, batch)
+        }, || pb.inc(1));
+        pb.finish_and_clear();
+
+        for wins in batch_wins.into_iter().collect::<Result<Vec<_>, _>>()? {
+            for (i, w) in wins.iter().enumerate() {
+                winrates[i].push(*w);
+            }
+        }
+
+        Ok(winrates.into_iter().map(calc_stats).collect())
+    }
+}
+
+// Average, range, lowest and highest of a player's winrates, and the games won in all batches
+type Stats = (f64, i64, i64, i64, u64);
+
+fn calc_stats(mut winrates: Vec<i64>) -> Stats {
     winrates.sort_unstable();
 
     // Convert to distribution
-    let lowest = winrates.first().unwrap(); 
+    let lowest = winrates.first().unwrap();
     let highest = winrates.last().unwrap();
     let dist_range = highest - lowest;
 
-    let avg = (winrates.iter().sum::<i64>() as f64) / (winrates.len() as f64);
+    let wins = winrates.iter().sum::<i64>();
+    let avg = (wins as f64) / (winrates.len() as f64);
+
+    (avg, dist_range, *lowest, *highest, wins as u64)
+}
+
+fn progress_bar(len: usize) -> ProgressBar {
+    let pb = ProgressBar::new(len as u64);
+    pb.set_style(ProgressStyle::default_bar().template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.green/blue}] {pos:>7}/{len:7} ({eta})").expect("progress bar template is valid"));
+    pb
+}
 
-    let mut lower: Vec<i64> = Vec::new();
-    for v in winrates.clone() {
-        if v > (avg as i64) {
-            break;
-        } else {
-            lower.push(v);
+// How the win rates are reported: the confidence level and the kind of interval
+#[derive(Serialize, Clone, Copy)]
+struct Confidence {
+    level: f64,
+    interval: Interval
+}
+
+impl Confidence {
+    fn new(level: f64, interval: Interval) -> Result<Self, String> {
+        if !(level > 0.0 && level < 1.0) {
+            return Err(format!("the confidence level has to lie between 0 and 1, not {}", level));
         }
+        Ok(Confidence { level, interval })
+    }
+
+    fn percent(&self) -> String {
+        format!("{}%", 100.0 * self.level)
     }
-    let avg_lower = (lower.iter().sum::<i64>() as f64) / (lower.len() as f64);
 
-    let mut higher: Vec<i64> = Vec::new();
-    for v in winrates.clone() {
-        if v < (avg as i64) {
-            continue;
-        } else {
-            higher.push(v);
+    fn significance(&self, p_value: f64) -> String {
+        match p_value < 1.0 - self.level {
+            true => format!("significant at {}", self.percent()),
+            false => format!("not significant at {}", self.percent())
         }
     }
-    let avg_higher = (higher.iter().sum::<i64>() as f64) / (higher.len() as f64);
+}
+
+// A win rate with its standard error and confidence interval
+#[derive(Serialize)]
+struct RateStats {
+    wins: u64,
+    games: u64,
+    win_rate: f64,
+    standard_error: f64,
+    lower: f64,
+    upper: f64
+}
+
+impl RateStats {
+    fn new(rate: WinRate, confidence: Confidence) -> Self {
+        let (lower, upper) = rate.interval(confidence.interval, confidence.level);
+        RateStats { wins: rate.wins, games: rate.games, win_rate: rate.rate(), standard_error: rate.standard_error(), lower, upper }
+    }
+}
+
+impl std::fmt::Display for RateStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:.2}% ± {:.2} ({:.2}% - {:.2}%)", 100.0 * self.win_rate, 100.0 * self.standard_error, 100.0 * self.lower, 100.0 * self.upper)
+    }
+}
+
+#[derive(Serialize)]
+struct PlayerStats {
+    player: String,
+    // Over all games
+    #[serde(flatten)]
+    win_rate: RateStats,
+    // Over the batches
+    avg: f64,
+    range: i64,
+    lowest: i64,
+    highest: i64
+}
 
-    (avg, dist_range, *lowest, avg_lower, *highest, avg_higher)
+// Exact binomial test whether one of two teams wins more than half of the games
+#[derive(Serialize)]
+struct Comparison {
+    better: String,
+    worse: String,
+    p_value: f64,
+    significant: bool
 }
 
-fn get_stats() -> (i64, Vec<(f64, i64, i64, f64, i64, f64)>) {
-    let player_num: i64 = 2;
-    let mut winrates: Vec<Vec<i64>> = vec![Vec::new(); player_num as usize];
-    let games: i64 = 100;
-    let rounds: i64 = 2000;
+#[derive(Serialize)]
+struct SimulationReport {
+    ruleset: Ruleset,
+    teams: Vec<i8>,
+    seed: u64,
+    games: usize,
+    // Games that ended without a winner
+    draws: u64,
+    confidence: Confidence,
+    stats: Vec<PlayerStats>,
+    comparison: Option<Comparison>,
+    seconds: u64
+}
+
+fn simulate(ctx: &Context, args: SimulateArgs) -> Result<(), String> {
+    let SimulateArgs { mut game, players: mut specs, games, batches, record_dir } = args;
+    let &Context { ref registry, ref runner, confidence, format } = ctx;
+    let now = Instant::now();
+    game.load(&mut specs, "simple,good")?;
+    check_players(registry, &specs)?;
+    let ruleset = game.ruleset(specs.len())?;
+    if games == 0 || batches == 0 {
+        return Err("at least one game has to be played".to_string());
+    }
+    let teams = game.teams(specs.len())?;
+    let seed = game.seed();
+    create_record_dir(&record_dir)?;
+
+    let simulation = Batches { registry, specs: &specs, ruleset, teams: &teams, games, record_dir: record_dir.as_deref() };
+    let stats = simulation.stats(batches, seed, runner)?;
+    let total = (games * batches) as u64;
 
-    let pb = ProgressBar::new(rounds as u64);
-    pb.set_style(ProgressStyle::default_bar().template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.green/blue}] {pos:>7}/{len:7} ({eta})"));
-    for _ in 1..rounds {
-        let wins = play_n_games(player_num, games as i32);
-        // get winrate of goodplayer
-        for (i, w) in wins.iter().enumerate() {
-            winrates[i].push(*w);
+    // The first player of each team stands for it, every game that is not a draw is won by one of the teams
+    let mut leaders: Vec<usize> = Vec::new();
+    for (i, t) in teams.iter().enumerate() {
+        if !leaders.iter().any(|l| teams[*l] == *t) {
+            leaders.push(i);
+        }
+    }
+    let draws = total - leaders.iter().map(|l| stats[*l].4).sum::<u64>();
+    let comparison = match leaders[..] {
+        [a, b] => {
+            let (better, worse) = if stats[a].4 >= stats[b].4 { (a, b) } else { (b, a) };
+            // Which player is better is only known after the games, so either of them could have come out ahead
+            let test = binomial_test(WinRate::new(stats[better].4, total - draws), 0.5, Alternative::TwoSided);
+            Some(Comparison { better: specs[better].to_string(), worse: specs[worse].to_string(), p_value: test.p_value, significant: test.significant(confidence.level) })
         }
+        _ => None
+    };
 
-        pb.inc(1);
+    let report = SimulationReport {
+        ruleset,
+        teams,
+        seed,
+        games: games * batches,
+        draws,
+        confidence,
+        stats: specs.iter().zip(stats).map(|(player, s)| PlayerStats { player: player.to_string(), win_rate: RateStats::new(WinRate::new(s.4, total), confidence), avg: s.0, range: s.1, lowest: s.2, highest: s.3 }).collect(),
+        comparison,
+        seconds: now.elapsed().as_secs()
+    };
+
+    match format {
+        Format::Json => print_json(&report),
+        Format::Text => {
+            println!("Games played: {} (seed {})", report.games, report.seed);
+            println!("Draws: {}\n", report.draws);
+
+            for (i, s) in report.stats.iter().enumerate() {
+                println!("PlayerNum: {} ({}) \nWin rate: {} \nAvg: {:.2} \nRange: {} \nLowest: {}  |  Highest: {}\n", i, s.player, s.win_rate, s.avg, s.range, s.lowest, s.highest);
+            }
+            if let Some(c) = &report.comparison {
+                println!("{} beats {}: p = {:.3e}, {}\n", c.better, c.worse, c.p_value, report.confidence.significance(c.p_value));
+            }
+
+            println!("Seconds elapsed: {}", report.seconds);
+        }
     }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct LeaderboardEntry {
+    player: String,
+    // TrueSkill rating: the conservative estimate mu - 3 sigma that the players are ranked by, the skill mu and its uncertainty sigma
+    rating: f64,
+    mu: f64,
+    sigma: f64,
+    #[serde(flatten)]
+    win_rate: RateStats
+}
+
+#[derive(Serialize)]
+struct TournamentReport {
+    ruleset: Ruleset,
+    seed: u64,
+    pairing: String,
+    seats: usize,
+    players: Vec<String>,
+    // Games played by a table in a round
+    games: usize,
+    confidence: Confidence,
+    // The players from the highest to the lowest rating
+    leaderboard: Vec<LeaderboardEntry>,
+    // met[a][b]: games player a and player b played at the same table, wins[a][b]: games of those won by player a
+    met: Vec<Vec<u64>>,
+    wins: Vec<Vec<u64>>,
+    // p_values[a][b]: exact two-sided binomial test whether player a and player b win equally often when they meet
+    p_values: Vec<Vec<f64>>
+}
+
+fn tournament(ctx: &Context, args: TournamentArgs) -> Result<(), String> {
+    let TournamentArgs { mut game, players: mut specs, games, seats, pairing } = args;
+    let &Context { ref registry, ref runner, confidence, format } = ctx;
+    game.load(&mut specs, "simple,good")?;
+    check_players(registry, &specs)?;
+    if specs.len() < 2 {
+        return Err("a tournament needs at least 2 players".to_string());
+    }
+    if seats < 2 || seats > specs.len() {
+        return Err(format!("{} players cannot fill tables of {} seats", specs.len(), seats));
+    }
+    let ruleset = game.ruleset(seats)?;
+    if !game.teams.is_empty() {
+        return Err("the players of a tournament play alone, teams cannot be given".to_string());
+    }
+    if games == 0 {
+        return Err("at least one game has to be played".to_string());
+    }
+    let seed = game.seed();
+
+    let mut t = Tournament::new(registry, specs.clone(), ruleset);
+    t.seats = seats;
+    t.pairing = pairing;
+    t.games = games;
+    t.runner = *runner;
+
+    let pb = progress_bar(t.total_games());
+    let standings = t.run(seed, || pb.inc(1)).map_err(|e| e.to_string())?;
     pb.finish_and_clear();
 
-    let mut stats: Vec<(f64, i64, i64, f64, i64, f64)> = Vec::new();
-    for w in winrates {
-        stats.push(calc_stats(w));
+    let n = specs.len();
+    let p_values = (0..n).map(|a| (0..n).map(|b| match standings.wins[a][b] + standings.wins[b][a] {
+        0 => 1.0,
+        decided => binomial_test(WinRate::new(standings.wins[a][b], decided), 0.5, Alternative::TwoSided).p_value
+    }).collect()).collect();
+    let leaderboard = standings.ranking().into_iter().map(|p| LeaderboardEntry {
+        player: specs[p].to_string(),
+        rating: standings.ratings[p].conservative(),
+        mu: standings.ratings[p].mu,
+        sigma: standings.ratings[p].sigma,
+        win_rate: RateStats::new(WinRate::new(standings.won[p], standings.games[p]), confidence)
+    }).collect();
+
+    let report = TournamentReport {
+        ruleset,
+        seed,
+        pairing: pairing.to_string(),
+        seats,
+        players: specs.iter().map(|s| s.to_string()).collect(),
+        games,
+        confidence,
+        leaderboard,
+        met: standings.met,
+        wins: standings.wins,
+        p_values
+    };
+    match format {
+        Format::Json => print_json(&report),
+        Format::Text => {
+            println!("{} with {} seats per table, {} games per table (seed {})\n", report.pairing, report.seats, report.games, report.seed);
+
+            let width = report.players.iter().map(|p| p.len()).max().unwrap_or(0).max(6);
+            println!("{:>4}  {:width$} {:>7} {:>7} {:>7} {:>7}  Win rate", "Rank", "Player", "Rating", "Mu", "Sigma", "Games", width = width);
+            for (rank, e) in report.leaderboard.iter().enumerate() {
+                println!("{:>4}  {:width$} {:>7.2} {:>7.2} {:>7.2} {:>7}  {}", rank + 1, e.player, e.rating, e.mu, e.sigma, e.win_rate.games, e.win_rate, width = width);
+            }
+
+            // How often the player of the row won the games he played with the player of the column
+            println!("\nHead to head:\n");
+            print!("{:width$}", "", width = width);
+            for p in &report.players {
+                print!(" {:>width$}", p, width = width);
+            }
+            println!();
+            for (a, p) in report.players.iter().enumerate() {
+                print!("{:width$}", p, width = width);
+                for b in 0..report.players.len() {
+                    if a == b || report.met[a][b] == 0 {
+                        print!(" {:>width$}", "-", width = width);
+                    } else {
+                        let mark = if report.p_values[a][b] < 1.0 - report.confidence.level { "*" } else { "" };
+                        print!(" {:>width$}", format!("{}{:.1}%", mark, 100.0 * report.wins[a][b] as f64 / report.met[a][b] as f64), width = width);
+                    }
+                }
+                println!();
+            }
+            println!("\n* the players do not win equally often against each other (significant at {})", report.confidence.percent());
+        }
     }
 
-    (games * rounds, stats)
+    Ok(())
 }
 
-fn main() {
+#[derive(Serialize)]
+struct DuplicatePlayerStats {
+    player: String,
+    #[serde(flatten)]
+    win_rate: RateStats,
+    // Deals the player won from every seat
+    sweeps: u64
+}
+
+#[derive(Serialize)]
+struct DuplicateReport {
+    ruleset: Ruleset,
+    seed: u64,
+    deals: usize,
+    games: usize,
+    confidence: Confidence,
+    stats: Vec<DuplicatePlayerStats>,
+    // Deals no player won from every seat, draws included
+    split: u64,
+    // Games that ended without a winner
+    draws: u64,
+    // Sign test on the deals swept by one of two players, the split deals were decided by the cards
+    comparison: Option<Comparison>,
+    seconds: u64
+}
+
+fn duplicate(ctx: &Context, args: DuplicateArgs) -> Result<(), String> {
+    let DuplicateArgs { mut game, players: mut specs, deals, record_dir } = args;
+    let &Context { ref registry, ref runner, confidence, format } = ctx;
     let now = Instant::now();
+    game.load(&mut specs, "simple,good")?;
+    check_players(registry, &specs)?;
+    let seats = specs.len();
+    let ruleset = game.ruleset(seats)?;
+    if !game.teams.is_empty() {
+        return Err("the players of a duplicate match play alone, teams cannot be given".to_string());
+    }
+    if deals == 0 {
+        return Err("at least one deal has to be played".to_string());
+    }
+    let seed = game.seed();
+    create_record_dir(&record_dir)?;
 
-    let (n, stats) = get_stats();
+    let mut d = DuplicateMatch::new(registry, specs.clone(), ruleset);
+    d.records = record_dir.is_some();
+    d.runner = *runner;
 
-    println!("Games played: {}\n", n);
+    let pb = progress_bar(deals);
+    let results = d.run(deals, seed, || pb.inc(1)).map_err(|e| e.to_string())?;
+    pb.finish_and_clear();
+    if let Some(dir) = &record_dir {
+        for (n, deal) in results.deals.iter().enumerate() {
+            for (r, record) in deal.records.iter().enumerate() {
+                write_record(dir, &format!("{}-{}", n, r), record)?;
+            }
+        }
+    }
+
+    let wins = results.wins();
+    let sweeps = results.sweeps();
+    let comparison = match seats {
+        2 => {
+            let (better, worse) = if sweeps[0] >= sweeps[1] { (0, 1) } else { (1, 0) };
+            // The deals the players split say nothing about which is better, and either of them could have swept more
+            let test = sign_test(sweeps[better], sweeps[worse], Alternative::TwoSided);
+            Some(Comparison { better: specs[better].to_string(), worse: specs[worse].to_string(), p_value: test.p_value, significant: test.significant(confidence.level) })
+        }
+        _ => None
+    };
+
+    let report = DuplicateReport {
+        ruleset,
+        seed,
+        deals,
+        games: deals * seats,
+        confidence,
+        stats: specs.iter().enumerate().map(|(p, spec)| DuplicatePlayerStats { player: spec.to_string(), win_rate: RateStats::new(WinRate::new(wins[p], (deals * seats) as u64), confidence), sweeps: sweeps[p] }).collect(),
+        split: results.split(),
+        draws: results.draws(),
+        comparison,
+        seconds: now.elapsed().as_secs()
+    };
+
+    match format {
+        Format::Json => print_json(&report),
+        Format::Text => {
+            println!("Deals: {}, every deal played {} times (seed {})", report.deals, seats, report.seed);
+            println!("Draws: {}\n", report.draws);
+
+            for (i, s) in report.stats.iter().enumerate() {
+                println!("PlayerNum: {} ({}) \nWin rate: {} \nDeals won from every seat: {}\n", i, s.player, s.win_rate, s.sweeps);
+            }
+            println!("Deals won by different players: {}\n", report.split);
+            if let Some(c) = &report.comparison {
+                println!("{} beats {} on the same cards: p = {:.3e}, {}\n", c.better, c.worse, c.p_value, report.confidence.significance(c.p_value));
+            }
+
+            println!("Seconds elapsed: {}", report.seconds);
+        }
+    }
+
+    Ok(())
+}
 
-    for (i, s) in stats.iter().enumerate() {
-        println!("PlayerNum: {} \nAvg: {:.2} \nRange: {} \nLowest: {}  |  Lower Avg: {:.2} \nHighest: {} |  Higher Avg: {:.2}\n", i, s.0, s.1, s.2, s.3, s.4, s.5);
+#[derive(Serialize)]
+struct ReplayReport {
+    players: Vec<String>,
+    turns: usize,
+    winner: i8
+}
+
+fn replay_file(file: String, format: Format) -> Result<(), String> {
+    let record = GameRecord::load(&file).map_err(|e| e.to_string())?;
+    let game = replay(&record).map_err(|e| e.to_string())?;
+
+    let report = ReplayReport { players: record.players.clone(), turns: record.turns.len(), winner: game.winner };
+    match format {
+        Format::Json => print_json(&report),
+        Format::Text => {
+            println!("{}\n", record.notation());
+            match usize::try_from(report.winner).ok().and_then(|w| report.players.get(w)) {
+                Some(name) => println!("Replayed {} turns, player {} ({}) won", report.turns, report.winner, name),
+                None if report.winner < 0 => println!("Replayed {} turns, the game ended in a draw", report.turns),
+                None => return Err(format!("the record is won by player {}, who is not in the game", report.winner))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn play(ctx: &Context, args: PlayArgs) -> Result<(), String> {
+    let PlayArgs { mut game, mut opponents, record } = args;
+    let &Context { ref registry, format, .. } = ctx;
+    game.load(&mut opponents, "good")?;
+    // The opponents are seeded from a stream of their own, so the same seed repeats the whole game
+    let seed = game.seed();
+    let mut players: Vec<Box<dyn Player>> = vec![Box::new(HumanPlayer {})];
+    players.extend(build_players(registry, &opponents, &mut StdRng::seed_from_u64(job_seed(seed, 0)))?);
+    let ruleset = game.ruleset(players.len())?;
+
+    let mut g = SkipBoGame::with_teams(ruleset, game.teams(players.len())?, seed);
+    let rounds = g.play_out(&players);
+    let winner = g.winner;
+
+    let names: Vec<String> = players.iter().map(|p| p.name()).collect();
+    let game_record = GameRecord::from_game(&g, names.clone());
+    if let Some(path) = &record {
+        game_record.save(path).map_err(|e| format!("{}: {}", path, e))?;
+    }
+    let report = ReplayReport { turns: game_record.turns.len(), players: names, winner };
+    match format {
+        Format::Json => print_json(&report),
+        Format::Text if winner < 0 => println!("\nNobody can play anymore, the game is a draw after {} rounds", rounds),
+        Format::Text if winner == 0 => println!("\nYou won after {} rounds!", rounds),
+        Format::Text => println!("\nPlayer {} ({}) won after {} rounds", winner, report.players[winner as usize], rounds)
     }
 
-    println!("Seconds elapsed: {}", now.elapsed().as_secs());
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SprtReport {
+    ruleset: Ruleset,
+    seed: u64,
+    players: Vec<PlayerSpec>,
+    #[serde(flatten)]
+    sprt: Sprt,
+    llr: f64,
+    lower_bound: f64,
+    upper_bound: f64,
+    decision: Decision,
+    // Games that ended without a winner, they are not part of the test
+    draws: u64,
+    // Win rate of the first player
+    win_rate: RateStats,
+    trace: Vec<SprtPoint>,
+    seconds: u64
+}
+
+fn sprt(ctx: &Context, args: SprtArgs) -> Result<(), String> {
+    let SprtArgs { mut game, players: mut specs, margin, alpha, beta, max_games, report_every } = args;
+    let &Context { ref registry, ref runner, confidence, format } = ctx;
+    let now = Instant::now();
+    game.load(&mut specs, "good,simple")?;
+    if specs.len() != 2 {
+        return Err(format!("the test compares 2 players, not {}", specs.len()));
+    }
+    check_players(registry, &specs)?;
+    let ruleset = game.ruleset(2)?;
+    if !game.teams.is_empty() {
+        return Err("the players of the test play alone, teams cannot be given".to_string());
+    }
+    if !(margin > 0.0 && margin < 0.5) {
+        return Err(format!("the margin has to lie between 0 and 0.5, not {}", margin));
+    }
+    if !(alpha > 0.0 && beta > 0.0 && alpha + beta < 1.0) {
+        return Err("alpha and beta have to be positive and below 1 together".to_string());
+    }
+    if report_every == 0 {
+        return Err("the test has to be reported at least every game".to_string());
+    }
+    let seed = game.seed();
+
+    let test = Sprt::new(0.5, 0.5 + margin, alpha, beta);
+    let (lower_bound, upper_bound) = test.bounds();
+    if format == Format::Text {
+        println!("H1: {} wins at least {:.1}% of the games against {} (seed {})", specs[0], 100.0 * test.p1, specs[1], seed);
+        println!("LLR bounds: {:.3} / {:.3}\n", lower_bound, upper_bound);
+    }
+
+    let mut m = SprtMatch::new(registry, specs.clone(), ruleset, test);
+    m.max_games = max_games;
+    m.report_every = report_every;
+    m.runner = *runner;
+    let results = m.run(seed, |point| if format == Format::Text {
+        println!("Games: {:>7}  Wins: {:>7}  LLR: {:>8.3}", point.games, point.wins, point.llr);
+    }).map_err(|e| e.to_string())?;
+    let test = results.test;
+
+    let report = SprtReport {
+        ruleset,
+        seed,
+        players: specs,
+        sprt: test,
+        llr: test.llr(),
+        lower_bound,
+        upper_bound,
+        decision: test.decision(),
+        draws: results.draws,
+        win_rate: RateStats::new(WinRate::new(test.wins, test.games()), confidence),
+        trace: results.trace,
+        seconds: now.elapsed().as_secs()
+    };
+
+    match format {
+        Format::Json => print_json(&report),
+        Format::Text => {
+            println!();
+            match report.decision {
+                Decision::AcceptH1 => println!("Accepted H1 after {} games: {} wins at least {:.1}% of the games against {}", report.sprt.games(), report.players[0], 100.0 * report.sprt.p1, report.players[1]),
+                Decision::AcceptH0 => println!("Accepted H0 after {} games: {} is not better than {}", report.sprt.games(), report.players[0], report.players[1]),
+                Decision::Continue => println!("No decision after {} games", report.sprt.games())
+            }
+            println!("Win rate of {}: {}, {} draws left out", report.players[0], report.win_rate, report.draws);
+            println!("\nSeconds elapsed: {}", report.seconds);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct TuningReport {
+    ruleset: Ruleset,
+    seed: u64,
+    opponents: Vec<PlayerSpec>,
+    iterations: usize,
+    // The best of the tuned and the starting player
+    player: PlayerSpec,
+    parameters: BTreeMap<String, f64>,
+    win_rate: f64,
+    start: PlayerSpec,
+    start_win_rate: f64,
+    games: usize,
+    confidence: Confidence,
+    // Interval of the win rate of the best player
+    lower: f64,
+    upper: f64,
+    // Sign test on the validation deals whether the tuned player wins more often than the starting one
+    p_value: f64,
+    seconds: u64
+}
+
+fn tune(ctx: &Context, args: TuneArgs) -> Result<(), String> {
+    let TuneArgs { mut game, player, params, mut opponents, iterations, games, validation, learning_rate, perturbation, output } = args;
+    let &Context { ref registry, runner, confidence, format } = ctx;
+    let now = Instant::now();
+    game.load(&mut opponents, &player.to_string())?;
+    check_players(registry, &opponents)?;
+    let ruleset = game.ruleset(opponents.len() + 1)?;
+    if !game.teams.is_empty() {
+        return Err("the tuned player plays alone, teams cannot be given".to_string());
+    }
+    if games == 0 || validation == 0 {
+        return Err("at least one game has to be played".to_string());
+    }
+
+    let mut parameters = default_parameters(&player).map_err(|e| e.to_string())?;
+    if let Some(unknown) = params.iter().find(|n| !parameters.iter().any(|p| p.name == **n)) {
+        return Err(format!("player \"{}\" has no parameter \"{}\" to tune", player.name, unknown));
+    }
+    if !params.is_empty() {
+        parameters.retain(|p| params.contains(&p.name));
+    }
+    if parameters.is_empty() {
+        return Err(format!("player \"{}\" has no parameters to tune", player.name));
+    }
+
+    let seed = game.seed();
+    let mut tuner = Tuner::new(registry, player.clone(), parameters, opponents.clone(), ruleset, iterations, seed);
+    tuner.games = games;
+    tuner.learning_rate = learning_rate;
+    tuner.perturbation = perturbation;
+    tuner.runner = runner;
+    let start = tuner.spec();
+
+    let pb = progress_bar(iterations);
+    for _ in 0..iterations {
+        tuner.step().map_err(|e| e.to_string())?;
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    // The tuned player has to beat the starting one on the same deals, a change within the noise may have made it worse
+    let deals = tuner.deals(validation);
+    let tuned = tuner.spec();
+    let tuned_results = tuner.results(&tuned, &deals).map_err(|e| e.to_string())?;
+    let start_results = tuner.results(&start, &deals).map_err(|e| e.to_string())?;
+    let wins = |results: &[bool]| WinRate::new(results.iter().filter(|w| **w).count() as u64, validation as u64);
+    let (win_rate, start_win_rate) = (wins(&tuned_results), wins(&start_results));
+    let (best, best_wins) = if win_rate.wins > start_win_rate.wins { (tuned, win_rate) } else { (start.clone(), start_win_rate) };
+    let (lower, upper) = best_wins.interval(confidence.interval, confidence.level);
+    // Only the deals won by exactly one of the two players tell them apart
+    let only = |a: &[bool], b: &[bool]| a.iter().zip(b).filter(|(x, y)| **x && !**y).count() as u64;
+    let test = sign_test(only(&tuned_results, &start_results), only(&start_results, &tuned_results), Alternative::Greater);
+
+    let report = TuningReport {
+        ruleset,
+        seed,
+        opponents,
+        iterations,
+        parameters: tuner.parameters().iter().map(|p| (p.name.clone(), best.param(&p.name, p.initial).expect("tuned values are numbers"))).collect(),
+        player: best,
+        win_rate: best_wins.rate(),
+        start,
+        start_win_rate: start_win_rate.rate(),
+        games: validation,
+        confidence,
+        lower,
+        upper,
+        p_value: test.p_value,
+        seconds: now.elapsed().as_secs()
+    };
+
+    if let Some(path) = &output {
+        fs::write(path, serde_json::to_string_pretty(&report).expect("report can be serialized")).map_err(|e| format!("cannot write {}: {}", path, e))?;
+    }
+
+    match format {
+        Format::Json => print_json(&report),
+        Format::Text => {
+            println!("Iterations: {} (seed {})\n", report.iterations, report.seed);
+            println!("Best player: {}", report.player);
+            println!("Win rate: {:.1}% over {} games ({:.1}% - {:.1}%), {:.1}% before tuning ({})", 100.0 * report.win_rate, report.games, 100.0 * report.lower, 100.0 * report.upper, 100.0 * report.start_win_rate, report.start);
+            println!("Tuning improved the win rate: p = {:.3}, {}", report.p_value, report.confidence.significance(report.p_value));
+            println!("\nSeconds elapsed: {}", report.seconds);
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let confidence = match Confidence::new(cli.confidence, cli.interval) {
+        Ok(c) => c,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            process::exit(1);
+        }
+    };
+    let ctx = Context { registry: PlayerRegistry::new(), runner: cli.threads.map_or_else(Runner::new, Runner::with_threads), confidence, format: cli.format };
+
+    let result = match cli.command {
+        Command::Simulate(args) => simulate(&ctx, args),
+        Command::Tournament(args) => tournament(&ctx, args),
+        Command::Duplicate(args) => duplicate(&ctx, args),
+        Command::Replay { file } => replay_file(file, ctx.format),
+        Command::Play(args) => play(&ctx, args),
+        Command::Sprt(args) => sprt(&ctx, args),
+        Command::Tune(args) => tune(&ctx, args)
+    };
+
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        process::exit(1);
+    }
 }
@@ -1,9 +1,16 @@
+use std::{fmt, str::FromStr};
+
+use crate::card::Card;
+
 #[derive(std::cmp::PartialEq, Debug, Copy, Clone)]
 pub enum CardStack {
     Stack,
     Side,
     Hand,
-    Field
+    Field,
+    // In team play the STACK and SIDE stacks of a partner, carrying the partner's player number
+    PartnerStack(i8),
+    PartnerSide(i8)
 }
 
 #[derive(Debug, std::cmp::PartialEq, Clone, Copy)]
@@ -12,4 +19,90 @@ pub struct Move {
     pub from_num: i8,
     pub to: CardStack,
     pub to_num: i8
-}
\ No newline at end of file
+}
+
+// Compact notation of a Move: S is the STACK, H the HAND, D the SIDE (discard) and F the FIELD stacks, followed by their number.
+// A partner's STACK or SIDE is prefixed with P and the partner's player number.
+// Ex: "S>F2" plays the STACK onto the third FIELD stack, "H1>D0" puts the second card from HAND on the first SIDE stack and "P2D1>F0" plays from the second SIDE stack of player 2
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let notation = |stack: CardStack, num: i8| match stack {
+            CardStack::Stack => "S".to_string(),
+            CardStack::Hand => format!("H{}", num),
+            CardStack::Side => format!("D{}", num),
+            CardStack::Field => format!("F{}", num),
+            CardStack::PartnerStack(partner) => format!("P{}S", partner),
+            CardStack::PartnerSide(partner) => format!("P{}D{}", partner, num)
+        };
+
+        write!(f, "{}>{}", notation(self.from, self.from_num), notation(self.to, self.to_num))
+    }
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |part: &str| -> Result<(CardStack, i8), String> {
+            if let Some(partner) = part.strip_prefix('P') {
+                let split = partner.find(|c: char| !c.is_ascii_digit()).ok_or(format!("missing partner stack in move \"{}\"", s))?;
+                let (partner, stack) = partner.split_at(split);
+                let partner = partner.parse::<i8>().map_err(|_| format!("invalid partner number in move \"{}\"", s))?;
+                if stack == "S" {
+                    return Ok((CardStack::PartnerStack(partner), 0));
+                }
+                let num = stack.strip_prefix('D').ok_or(format!("unknown partner stack in move \"{}\"", s))?;
+                let num = num.parse::<i8>().map_err(|_| format!("invalid stack number in move \"{}\"", s))?;
+                return Ok((CardStack::PartnerSide(partner), num));
+            }
+
+            let stack = match part.chars().next() {
+                Some('S') => return Ok((CardStack::Stack, 0)),
+                Some('H') => CardStack::Hand,
+                Some('D') => CardStack::Side,
+                Some('F') => CardStack::Field,
+                _ => return Err(format!("unknown stack in move \"{}\"", s))
+            };
+            let num = part[1..].parse::<i8>().map_err(|_| format!("invalid stack number in move \"{}\"", s))?;
+            Ok((stack, num))
+        };
+
+        let (from, to) = s.split_once('>').ok_or(format!("missing '>' in move \"{}\"", s))?;
+        let (from, from_num) = parse(from)?;
+        let (to, to_num) = parse(to)?;
+
+        Ok(Move { from, from_num, to, to_num })
+    }
+}
+
+// Reasons for the game to reject a Move
+#[derive(Debug, std::cmp::PartialEq, Clone, Copy)]
+pub enum MoveError {
+    // The source or destination stack does not exist
+    IndexOutOfRange { stack: CardStack, index: i8 },
+    // There is no card on the source stack
+    EmptySource(CardStack),
+    // The card does not fit onto the FIELD stack
+    IllegalCardForPile { card: Card, pile: i8 },
+    // Cards can only be moved from STACK, HAND or SIDE to FIELD and from HAND to SIDE
+    IllegalRoute { from: CardStack, to: CardStack },
+    // At the end of his turn a player has to put a card on SIDE, not on FIELD
+    DiscardToField,
+    // Cards can only be taken from the stacks of a player on the same team
+    NotPartner(i8)
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::IndexOutOfRange { stack, index } => write!(f, "{:?} has no stack number {}", stack, index),
+            MoveError::EmptySource(stack) => write!(f, "{:?} has no card to play", stack),
+            MoveError::IllegalCardForPile { card, pile } => write!(f, "{:?} cannot be played onto Field stack {}", card, pile),
+            MoveError::IllegalRoute { from, to } => write!(f, "cards cannot be moved from {:?} to {:?}", from, to),
+            MoveError::DiscardToField => write!(f, "the card ending the turn has to be put on Side"),
+            MoveError::NotPartner(player) => write!(f, "player {} is not a partner", player)
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
@@ -1,40 +1,75 @@
-use crate::{players::player::Player, move_stack::{Move, CardStack}};
+use crate::{players::player::Player, move_stack::{Move, CardStack}, card::Card, building_pile::BuildingPile, game_view::GameView, ruleset::Ruleset};
 
-// Calculate the amount of cards needed to go from one card to another
-fn distance_between_cards(c1: i8, c2: i8) -> i8 {
+// Calculate the amount of cards needed to go from one card to another, going around after the highest card
+pub(crate) fn distance_between_cards(c1: i8, c2: i8, highest_card: u8) -> i8 {
     if c2 < c1 {
-        12 - c1 + c2
+        highest_card as i8 - c1 + c2
     } else {
         c2 - c1
     }
 }
 
+// Value of the visible card of a FIELD stack, 0 if the stack is empty or complete
+pub(crate) fn pile_value(pile: &BuildingPile, highest_card: u8) -> i8 {
+    pile.top_value().map_or(0, |v| (v % highest_card) as i8)
+}
+
+// Check if a card is a numbered card with the given value
+fn is_number(card: &Card, value: i8) -> bool {
+    card.value().map(|v| v as i8) == Some(value)
+}
+
+// Check if playing a card would bring a FIELD stack closer to the opponent's STACK (at most help_distance cards away)
+fn helps_opponent(card: &Card, opponent_stack: Option<Card>, help_distance: i8, highest_card: u8) -> bool {
+    match (card.value(), opponent_stack.and_then(|c| c.value())) {
+        (Some(c), Some(o)) => distance_between_cards(c as i8, o as i8, highest_card) <= help_distance,
+        // The opponent can play a wild from his STACK anyway
+        (Some(_), None) => false,
+        (None, _) => true
+    }
+}
+
 trait RecursivePlayer {
+    #[allow(clippy::too_many_arguments)]
     fn recurse_stack (
         &self, 
         stack: i8, 
-        playing_field: (i8, bool), 
+        playing_field: &BuildingPile, 
         playing_field_stack: i8, 
-        hand: Vec<i8>, 
-        fixed_hand: Vec<i8>, 
-        side: [Vec<i8>; 4], 
-        fixed_side: [Vec<i8>; 4], 
+        ruleset: &Ruleset, 
+        hand: Vec<Card>, 
+        fixed_hand: Vec<Card>, 
+        side: Vec<Vec<Card>>, 
         used_stack: bool, 
         used_joker: bool
     ) -> Option<Move>;
 }
 
-pub struct GoodPlayer {}
+pub struct GoodPlayer {
+    // Cards kept back from FIELD if the opponent's STACK card is at most this many cards above them
+    pub help_distance: i8,
+    // FIELD stacks less than this many cards below the opponent's STACK card are played on to block the opponent
+    pub block_distance: i8
+}
+
+impl Default for GoodPlayer {
+    fn default() -> Self {
+        GoodPlayer { help_distance: 3, block_distance: 4 }
+    }
+}
+
 impl RecursivePlayer for GoodPlayer {
     // TODO: Recurse feed forward
     // Recursively check if the player could play a card from STACK by using the other availiable cards
-    fn recurse_stack (&self, stack: i8, playing_field: (i8, bool), playing_field_stack: i8, mut hand: Vec<i8>, fixed_hand: Vec<i8>, mut side: [Vec<i8>; 4], fixed_side: [Vec<i8>; 4], used_stack: bool, used_joker: bool) -> Option<Move> {
+    fn recurse_stack (&self, stack: i8, playing_field: &BuildingPile, playing_field_stack: i8, ruleset: &Ruleset, mut hand: Vec<Card>, fixed_hand: Vec<Card>, mut side: Vec<Vec<Card>>, used_stack: bool, used_joker: bool) -> Option<Move> {
+        let field_value = pile_value(playing_field, ruleset.highest_card);
+
         // Is any card from HAND a card that could come before stack
-        match hand.iter().position(|c| c == &(stack - 1)) {
+        match hand.iter().position(|c| is_number(c, stack - 1)) {
             None => {}
             Some(card) => {
                 // If the player has all necessary cards to reach his STACK, return the first move
-                if (stack-2) == playing_field.0 {
+                if (stack-2) == field_value {
                     match fixed_hand.iter().position(|c| *c == hand[card]) {
                         None => {},
                         Some(card_pos) => {  
@@ -46,7 +81,7 @@ impl RecursivePlayer for GoodPlayer {
                     hand.remove(card);
 
                     // If the player has the card, check if he also has a card that comes before that card (lower the value of stack to that of the current card)
-                    return self.recurse_stack(stack - 1, playing_field, playing_field_stack, hand, fixed_hand, side, fixed_side, used_stack, false);
+                    return self.recurse_stack(stack - 1, playing_field, playing_field_stack, ruleset, hand, fixed_hand, side, used_stack, false);
                 }
             }
         }
@@ -55,32 +90,30 @@ impl RecursivePlayer for GoodPlayer {
         if !used_stack {
             for (index, side_stack) in side.iter_mut().enumerate() {
                 match side_stack.last() {
-                    None => {}
-                    Some(s) => {
-                        if *s == (stack - 1) || *s == -1 {
-                            // If the player has all necessary cards to reach his STACK, return the first move
-                            if (stack-2) == playing_field.0 && /* If the card is a joker, check if joker is allowed */((s == &-1) ^ playing_field.1) {
-                                return Some(Move { to: CardStack::Field, to_num: playing_field_stack, from: CardStack::Side, from_num: index as i8 });
-                            } else {
-                                // remove that card from side
-                                side_stack.pop().unwrap();
-
-                                // If the player has the card, check if he also has a card that comes before that card (lower the value of stack to that of the current card)
-                                return self.recurse_stack(stack - 1, playing_field, playing_field_stack, hand, fixed_hand, side, fixed_side, true, false);
-                            }
+                    Some(s) if is_number(s, stack - 1) || s.is_wild() => {
+                        // If the player has all necessary cards to reach his STACK, return the first move
+                        if (stack-2) == field_value && /* A joker may not be allowed on the stack */playing_field.accepts(*s, ruleset) {
+                            return Some(Move { to: CardStack::Field, to_num: playing_field_stack, from: CardStack::Side, from_num: index as i8 });
+                        } else {
+                            // remove that card from side
+                            side_stack.pop().unwrap();
+
+                            // If the player has the card, check if he also has a card that comes before that card (lower the value of stack to that of the current card)
+                            return self.recurse_stack(stack - 1, playing_field, playing_field_stack, ruleset, hand, fixed_hand, side, true, false);
                         }
                     }
+                    _ => {}
                 };
             }
         }
 
         // Is there any joker in HAND?
         if !used_joker {
-            match hand.iter().position(|c| c == &-1) {
+            match hand.iter().position(|c| c.is_wild()) {
                 None => {}
                 Some(card) => {
                     // If the player has all necessary cards to reach his STACK, return the first move
-                    if (stack-2) == playing_field.0 && !playing_field.1 {
+                    if (stack-2) == field_value && playing_field.accepts(hand[card], ruleset) {
                         match fixed_hand.iter().position(|c| *c == hand[card]) {
                             None => {},
                             Some(card_pos) => {  
@@ -93,7 +126,7 @@ impl RecursivePlayer for GoodPlayer {
                         hand.remove(card);
                         
                         // If the player has the card, check if he also has a card that comes before that card (lower the value of stack to that of the current card)
-                        return self.recurse_stack(stack - 1, playing_field, playing_field_stack, hand, fixed_hand, side, fixed_side, used_stack, true);
+                        return self.recurse_stack(stack - 1, playing_field, playing_field_stack, ruleset, hand, fixed_hand, side, used_stack, true);
                     }
                 }
             }
@@ -104,61 +137,75 @@ impl RecursivePlayer for GoodPlayer {
 }
 
 impl Player for GoodPlayer {
-    fn select_move(&self, moves: Vec<Move>, stack: i8, opponent_stack: i8, side: [Vec<i8>; 4], hand: Vec<i8>, playing_field: [(i8, bool); 4]) -> Option<Move> {
-        // If the player can play any card from STACK, do so
+    fn name(&self) -> String {
+        "good".to_string()
+    }
+
+    fn select_move(&self, moves: &[Move], view: &GameView) -> Option<Move> {
+        let hand = view.hand.to_vec();
+        let side = view.side.to_vec();
+        let opponent_stack = view.next_opponent().and_then(|o| o.stack_top);
+        let highest_card = view.ruleset.highest_card;
+
+        // If the player can play any card from STACK, do so. In team play the same goes for a partner's STACK
         let first_move = moves.first().unwrap();
         if first_move.from == CardStack::Stack {
             return Some(*first_move);
         }
-
-        // Play cards from HAND & SIDE if the player can play a card from STACK
-        for (index, p) in playing_field.iter().enumerate() {
-            // recursively iterate through the cards in hand and side
-            let m = self.recurse_stack(stack, *p, index as i8, hand.clone(), hand.clone(), side.clone(), side.clone(), false, false);
-            if m.is_some() {
-                return m
-            };
+        if let Some(m) = moves.iter().find(|m| matches!(m.from, CardStack::PartnerStack(_))) {
+            return Some(*m);
         }
 
-        // Play cards from HAND & SIDE if you can prevent the next player from playing a card from stack (max 3 cards played)
-        for (index, p) in playing_field.iter().enumerate() {
-            // recursively iterate through the cards in hand and side
-            if distance_between_cards(p.0, opponent_stack) < 4 {
-                let m = self.recurse_stack(opponent_stack+1, *p, index as i8, hand.clone(), hand.clone(), side.clone(), side.clone(), false, false);
+        // Play cards from HAND & SIDE if the player can play a card from STACK (a wild on STACK needs no preparation)
+        if let Some(Card::Number(stack)) = view.stack_top {
+            for (index, p) in view.playing_field.iter().enumerate() {
+                // recursively iterate through the cards in hand and side
+                let m = self.recurse_stack(stack as i8, p, index as i8, view.ruleset, hand.clone(), hand.clone(), side.clone(), false, false);
                 if m.is_some() {
                     return m
                 };
             }
         }
 
-
-        // Play cards from HAND that are not jokers or cards whose values are less than OPPONENT_STACK - 3 (they would help the opponent)
-        let vc: Vec<&Move> = moves.iter().filter(|m| m.from == CardStack::Hand).filter(|m| distance_between_cards(hand[m.from_num as usize], opponent_stack) > 3).collect();
-        if !vc.is_empty() {
-            for m in vc {
-                // Check if card is joker
-                if hand[m.from_num as usize] != -1 {
-                    return Some(*m);
+        // Play cards from HAND & SIDE if you can prevent the next player from playing a card from stack (less than block_distance cards played)
+        if let Some(Card::Number(opponent_stack)) = opponent_stack {
+            for (index, p) in view.playing_field.iter().enumerate() {
+                // recursively iterate through the cards in hand and side
+                if distance_between_cards(pile_value(p, highest_card), opponent_stack as i8, highest_card) < self.block_distance {
+                    let m = self.recurse_stack(opponent_stack as i8 + 1, p, index as i8, view.ruleset, hand.clone(), hand.clone(), side.clone(), false, false);
+                    if m.is_some() {
+                        return m
+                    };
                 }
             }
         }
 
-        // Play cards from SIDE that are not jokers or cards whose values are less than OPPONENT_STACK - 3 (otherwise they would help the opponent)
-        let vc: Vec<&Move> = moves.iter().filter(|m| m.from == CardStack::Side).filter(|m| distance_between_cards(*side[m.from_num as usize].last().unwrap(), opponent_stack) > 3).collect();
-        if !vc.is_empty() {
-            for m in vc {
-                // Check if card is joker
-                if *side[m.from_num as usize].last().unwrap() != -1 {
-                    return Some(*m);
-                }
-            }
+
+        // Play cards from HAND that are not jokers or cards whose values are less than OPPONENT_STACK - help_distance (they would help the opponent)
+        let vc: Vec<&Move> = moves.iter().filter(|m| m.from == CardStack::Hand).filter(|m| !helps_opponent(&hand[m.from_num as usize], opponent_stack, self.help_distance, highest_card)).collect();
+        if let Some(m) = vc.first() {
+            return Some(**m);
+        }
+
+        // Play cards from SIDE that are not jokers or cards whose values are less than OPPONENT_STACK - help_distance (otherwise they would help the opponent)
+        let vc: Vec<&Move> = moves.iter().filter(|m| m.from == CardStack::Side).filter(|m| !helps_opponent(side[m.from_num as usize].last().unwrap(), opponent_stack, self.help_distance, highest_card)).collect();
+        if let Some(m) = vc.first() {
+            return Some(**m);
+        }
+
+        // With an empty HAND there is nothing left to draw, holding cards back would only stall the game
+        if hand.is_empty() {
+            return moves.first().copied();
         }
 
         // No move corresponds to the criteria and the player chooses not to play any card
         None
     }
 
-    fn select_stack(&self, hand: Vec<i8>, side: [std::vec::Vec<i8>; 4]) -> Move {
+    fn select_stack(&self, view: &GameView) -> Move {
+        let hand = view.hand;
+        let side = view.side;
+
         // Play card to stack that already has that card
         for (index_s, s) in side.iter().enumerate() {
             match s.last() {    
@@ -197,12 +244,10 @@ impl Player for GoodPlayer {
                 for (index_h, h) in hand.iter().enumerate() {
                     for (index_s, s) in side.iter().enumerate() {
                         match s.last() {
-                            None => {}
-                            Some(side_stack) => {
-                                if h+1 == *side_stack {
-                                    return Move { from: CardStack::Hand, from_num: index_h as i8, to: CardStack::Side, to_num: index_s as i8 };
-                                }
+                            Some(side_stack) if h.value().is_some_and(|v| is_number(side_stack, v as i8 + 1)) => {
+                                return Move { from: CardStack::Hand, from_num: index_h as i8, to: CardStack::Side, to_num: index_s as i8 };
                             }
+                            _ => {}
                         }
                     }
                 }
@@ -0,0 +1,78 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{players::player::Player, move_stack::{Move, CardStack}, game_view::GameView};
+
+// Print the table as the player sees it
+fn print_view(view: &GameView) {
+    let cards = |cards: &[crate::card::Card]| cards.iter().map(|c| c.to_string()).collect::<Vec<String>>().join(" ");
+    let tops = |side: &[Vec<crate::card::Card>]| side.iter().map(|s| s.last().map_or("-".to_string(), |c| c.to_string())).collect::<Vec<String>>().join(" ");
+
+    println!();
+    println!("Turn {} | draw pile: {} cards", view.turn, view.draw_pile_size);
+    for o in &view.opponents {
+        println!("Player {}: stack {} ({} cards) | side {}", o.player_num, o.stack_top.map_or("-".to_string(), |c| c.to_string()), o.stack_size, tops(o.side));
+    }
+    println!("Field: {}", view.playing_field.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(" "));
+    println!("You (player {}): stack {} ({} cards) | side {} | hand {}", view.player_num, view.stack_top.map_or("-".to_string(), |c| c.to_string()), view.stack_size, tops(view.side), cards(view.hand));
+}
+
+// Read one line from the terminal, None if the input ended
+fn read_line(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().ok()?;
+
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string())
+    }
+}
+
+pub struct HumanPlayer {}
+// Lets a person play in the terminal. Moves are entered in the compact notation (ex: "H2>F0") or by their number in the list
+impl Player for HumanPlayer {
+    fn name(&self) -> String {
+        "human".to_string()
+    }
+
+    fn select_move(&self, moves: &[Move], view: &GameView) -> Option<Move> {
+        print_view(view);
+        for (index, m) in moves.iter().enumerate() {
+            println!("  [{}] {}", index, m);
+        }
+
+        loop {
+            let input = read_line("Move (number or notation, empty to end the turn): ")?;
+            if input.is_empty() {
+                return None;
+            }
+
+            let selected = match input.parse::<usize>() {
+                Ok(index) => moves.get(index).copied(),
+                Err(_) => input.parse::<Move>().ok().filter(|m| moves.contains(m))
+            };
+            match selected {
+                Some(m) => return Some(m),
+                None => println!("\"{}\" is not one of the moves above", input)
+            }
+        }
+    }
+
+    fn select_stack(&self, view: &GameView) -> Move {
+        print_view(view);
+
+        loop {
+            // Without input the first card is put on the first SIDE stack
+            let input = match read_line("Card to put on side (ex: \"H2>D0\", empty for \"H0>D0\"): ") {
+                Some(input) if !input.is_empty() => input,
+                _ => return Move { from: CardStack::Hand, from_num: 0, to: CardStack::Side, to_num: 0 }
+            };
+
+            match input.parse::<Move>() {
+                Ok(m) if m.from == CardStack::Hand && m.to == CardStack::Side
+                    && (0..view.hand.len() as i8).contains(&m.from_num) && (0..view.side.len() as i8).contains(&m.to_num) => return m,
+                _ => println!("\"{}\" does not put a card from your hand on one of your side stacks", input)
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{game::{Game, SkipBoGame}, players::{player::Player, mcts_player::{Action, SearchConfig, turn_actions, step, suggested_action, choose_action, field_move, side_move}}, move_stack::{Move, CardStack}, card::Card, game_view::GameView, determinization::HiddenCards};
+
+// An action as it is found in every deal of the hidden cards: a card from HAND is known by its value, its position in HAND
+// depends on the cards drawn during the turn
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct ActionKey {
+    action: Action,
+    card: Option<Card>
+}
+
+fn action_key(game: &SkipBoGame, player_num: i8, action: Action) -> ActionKey {
+    match action {
+        Action::Play(mut m) if m.from == CardStack::Hand => {
+            let card = game.players[player_num as usize].hand.get(m.from_num as usize).copied();
+            m.from_num = 0;
+            ActionKey { action: Action::Play(m), card }
+        }
+        _ => ActionKey { action, card: None }
+    }
+}
+
+struct Node {
+    parent: Option<usize>,
+    key: Option<ActionKey>,
+    children: Vec<usize>,
+    visits: u32,
+    // Number of iterations in which the action could have been chosen
+    availability: u32,
+    value: f64
+}
+
+impl Node {
+    fn new(parent: Option<usize>, key: Option<ActionKey>) -> Self {
+        Node { parent, key, children: Vec::new(), visits: 0, availability: 0, value: 0.0 }
+    }
+}
+
+// Searches the moves of its turn with Information Set Monte Carlo Tree Search. All iterations share one tree, but every iteration
+// deals the cards it cannot see anew, so the tree holds what the player knows instead of one guess of the hidden cards.
+// Cards drawn during the turn differ between the deals, so only the actions possible in the current deal are selected and
+// UCT weighs every action by the number of iterations it was available in
+pub struct IsmctsPlayer {
+    pub search: SearchConfig,
+    rng: RefCell<StdRng>
+}
+
+impl IsmctsPlayer {
+    pub fn new(seed: u64) -> Self {
+        IsmctsPlayer { search: SearchConfig::default(), rng: RefCell::new(StdRng::seed_from_u64(seed)) }
+    }
+
+    fn uct(&self, node: &Node) -> f64 {
+        self.search.uct(node.value, node.visits, node.availability)
+    }
+
+    fn find_action(&self, view: &GameView, discard_only: bool) -> Option<Action> {
+        let mut rng = self.rng.borrow_mut();
+        let player_num = view.player_num;
+        let policy = self.search.rollout.player(&mut rng);
+
+        // The moves of the root only depend on the visible cards, they are the same in every deal
+        let root_game = SkipBoGame::from_view(view, HiddenCards::sample(view, &mut *rng), rng.gen());
+        let actions = turn_actions(&root_game, player_num, discard_only);
+        if actions.len() <= 1 {
+            return actions.first().copied();
+        }
+
+        let budget = self.search.budget();
+        let mut nodes = vec![Node::new(None, None)];
+        let mut iteration = 0;
+        while budget.allows(iteration) {
+            iteration += 1;
+            let mut game = SkipBoGame::from_view(view, HiddenCards::sample(view, &mut *rng), rng.gen());
+            let mut node = 0;
+            let mut turn_over = false;
+            let mut discard = discard_only;
+
+            while !turn_over {
+                let legal: Vec<(Action, ActionKey)> = turn_actions(&game, player_num, discard).into_iter().map(|a| (a, action_key(&game, player_num, a))).collect();
+                discard = false;
+
+                // Expansion: try one action that was never tried from this node
+                let untried: Vec<&(Action, ActionKey)> = legal.iter().filter(|(_, k)| !nodes[node].children.iter().any(|c| nodes[*c].key == Some(*k))).collect();
+                if !untried.is_empty() {
+                    let (action, key) = *untried[rng.gen_range(0..untried.len())];
+                    turn_over = step(&mut game, player_num, &action, policy.as_ref());
+                    nodes.push(Node::new(Some(node), Some(key)));
+                    let child = nodes.len() - 1;
+                    nodes[node].children.push(child);
+                    node = child;
+                    break;
+                }
+
+                // Selection: the best UCT child among the actions of this deal, every one of them was available once more
+                let available: Vec<usize> = nodes[node].children.iter().copied().filter(|c| legal.iter().any(|(_, k)| nodes[*c].key == Some(*k))).collect();
+                for c in &available {
+                    nodes[*c].availability += 1;
+                }
+                node = available.into_iter().max_by(|a, b| self.uct(&nodes[*a]).total_cmp(&self.uct(&nodes[*b]))).unwrap();
+                let action = legal.iter().find(|(_, k)| nodes[node].key == Some(*k)).unwrap().0;
+                turn_over = step(&mut game, player_num, &action, policy.as_ref());
+            }
+
+            // Rollout and backpropagation
+            let value = self.search.simulate(&mut game, player_num, turn_over, policy.as_ref());
+            let mut current = Some(node);
+            while let Some(n) = current {
+                nodes[n].visits += 1;
+                nodes[n].value += value;
+                current = nodes[n].parent;
+            }
+        }
+
+        let stats: Vec<(u32, f64)> = actions.iter().map(|a| {
+            let key = action_key(&root_game, player_num, *a);
+            nodes[0].children.iter().find(|c| nodes[**c].key == Some(key)).map_or((0, 0.0), |c| (nodes[*c].visits, nodes[*c].value))
+        }).collect();
+
+        let suggested = suggested_action(&root_game, view, discard_only, policy.as_ref());
+        choose_action(&root_game, player_num, &actions, stats, suggested, self.search.prior, &mut rng)
+    }
+}
+
+impl Player for IsmctsPlayer {
+    fn name(&self) -> String {
+        "ismcts".to_string()
+    }
+
+    fn select_move(&self, _moves: &[Move], view: &GameView) -> Option<Move> {
+        field_move(self.find_action(view, false))
+    }
+
+    fn select_stack(&self, view: &GameView) -> Move {
+        side_move(self.find_action(view, true))
+    }
+}
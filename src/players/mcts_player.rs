@@ -0,0 +1,410 @@
+use std::{cell::RefCell, str::FromStr, time::{Duration, Instant}};
+
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+use crate::{game::{Game, SkipBoGame}, players::{player::Player, simple_player::SimplePlayer, good_player::GoodPlayer, random_player::RandomPlayer}, move_stack::{Move, CardStack}, game_view::GameView, determinization::HiddenCards, evaluation::{Evaluator, LinearEvaluator}};
+
+// How the game is played on in a rollout once the searched turn is over
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rollout {
+    Random,
+    Simple,
+    Good
+}
+
+impl FromStr for Rollout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Rollout::Random),
+            "simple" => Ok(Rollout::Simple),
+            "good" => Ok(Rollout::Good),
+            _ => Err(format!("unknown rollout \"{}\"", s))
+        }
+    }
+}
+
+impl Rollout {
+    pub(crate) fn player(&self, rng: &mut StdRng) -> Box<dyn Player> {
+        match self {
+            Rollout::Random => Box::new(RandomPlayer::new(rng.gen())),
+            Rollout::Simple => Box::new(SimplePlayer {}),
+            Rollout::Good => Box::new(GoodPlayer::default())
+        }
+    }
+}
+
+// A decision of the searched turn: playing a card onto FIELD or putting a card on SIDE. Ending the turn leaves
+// the choice of the card for SIDE to the rollout player
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum Action {
+    Play(Move),
+    EndTurn
+}
+
+struct Node {
+    parent: Option<usize>,
+    action: Option<Action>,
+    children: Vec<usize>,
+    untried: Vec<Action>,
+    visits: u32,
+    value: f64
+}
+
+impl Node {
+    fn new(parent: Option<usize>, action: Option<Action>, untried: Vec<Action>) -> Self {
+        Node { parent, action, children: Vec::new(), untried, visits: 0, value: 0.0 }
+    }
+}
+
+// The settings of a tree search, shared by MctsPlayer and IsmctsPlayer
+pub struct SearchConfig {
+    // Number of iterations per decision, unless a time limit is set
+    pub iterations: usize,
+    pub time_limit: Option<Duration>,
+    // Weight of the exploration term of UCT
+    pub exploration: f64,
+    pub rollout: Rollout,
+    // Number of turns played after the searched turn before the game is rated
+    pub depth: usize,
+    // Wins credited to the move the rollout player would choose
+    pub prior: u32,
+    // Rates the game after the rollout if it is not over yet, compared between the teams
+    pub evaluator: Box<dyn Evaluator>
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            iterations: 500,
+            time_limit: None,
+            exploration: std::f64::consts::SQRT_2,
+            rollout: Rollout::Good,
+            depth: 8,
+            prior: 50,
+            evaluator: Box::new(LinearEvaluator::cards_left())
+        }
+    }
+}
+
+impl SearchConfig {
+    // The parameters of a player spec that set the search, besides the evaluator weights
+    pub const PARAMS: [&'static str; 6] = ["iterations", "time", "exploration", "rollout", "depth", "prior"];
+
+    // The budget of a search that starts now
+    pub(crate) fn budget(&self) -> Budget {
+        match self.time_limit {
+            Some(limit) => Budget::Until(Instant::now() + limit),
+            None => Budget::Iterations(self.iterations)
+        }
+    }
+
+    // UCT value of a node from its summed value and visits, the exploration term grows with the visits of its parent
+    pub(crate) fn uct(&self, value: f64, visits: u32, parent_visits: u32) -> f64 {
+        value / visits as f64 + self.exploration * ((parent_visits as f64).ln() / visits as f64).sqrt()
+    }
+
+    // Finish the searched turn, play the following turns with the rollout player and rate the game
+    pub(crate) fn simulate(&self, game: &mut SkipBoGame, player_num: i8, turn_over: bool, policy: &dyn Player) -> f64 {
+        simulate(game, player_num, turn_over, self.depth, policy, self.evaluator.as_ref())
+    }
+}
+
+// The move of a searched action for Player::select_move, ending the turn is followed by a search for the card to put on SIDE
+// in select_stack
+pub(crate) fn field_move(action: Option<Action>) -> Option<Move> {
+    match action {
+        Some(Action::Play(m)) => Some(m),
+        _ => None
+    }
+}
+
+// The move of a searched action for Player::select_stack
+pub(crate) fn side_move(action: Option<Action>) -> Move {
+    match action {
+        Some(Action::Play(m)) => m,
+        _ => Move { from: CardStack::Hand, from_num: 0, to: CardStack::Side, to_num: 0 }
+    }
+}
+
+// How long a search tree is grown
+#[derive(Clone, Copy)]
+pub(crate) enum Budget {
+    Iterations(usize),
+    Until(Instant)
+}
+
+impl Budget {
+    pub(crate) fn allows(&self, iteration: usize) -> bool {
+        match self {
+            Budget::Iterations(n) => iteration < *n,
+            Budget::Until(deadline) => Instant::now() < *deadline
+        }
+    }
+}
+
+// The decisions to search in the current state: every valid move and ending the turn, or only the cards that can be put on SIDE.
+// Moves that lead to the same state (the same card from HAND, an empty SIDE stack like another) are only listed once
+pub(crate) fn turn_actions(game: &SkipBoGame, player_num: i8, discard_only: bool) -> Vec<Action> {
+    let p = &game.players[player_num as usize];
+    let mut actions: Vec<Action> = Vec::new();
+
+    if discard_only {
+        for (index_h, card) in p.hand.iter().enumerate() {
+            if p.hand[..index_h].contains(card) {
+                continue;
+            }
+            for (index_s, side) in p.side.iter().enumerate() {
+                if side.is_empty() && p.side[..index_s].iter().any(|s| s.is_empty()) {
+                    continue;
+                }
+                actions.push(Action::Play(Move { from: CardStack::Hand, from_num: index_h as i8, to: CardStack::Side, to_num: index_s as i8 }));
+            }
+        }
+        return actions
+    }
+
+    let mut moves: Vec<Move> = Vec::new();
+    for m in game.get_valid_moves(player_num) {
+        let duplicate = m.from == CardStack::Hand && moves.iter().any(|a| a.from == CardStack::Hand && a.to_num == m.to_num && p.hand[a.from_num as usize] == p.hand[m.from_num as usize]);
+        if !duplicate {
+            moves.push(m);
+        }
+    }
+    actions.extend(moves.into_iter().map(Action::Play));
+    actions.push(Action::EndTurn);
+
+    actions
+}
+
+// The action turn_actions lists for a move that leads to the same state
+pub(crate) fn canonical_action(game: &SkipBoGame, player_num: i8, action: Action) -> Action {
+    let Action::Play(mut m) = action else { return action };
+    let p = &game.players[player_num as usize];
+    if m.from == CardStack::Hand {
+        if let Some(card) = p.hand.get(m.from_num as usize) {
+            m.from_num = p.hand.iter().position(|c| c == card).unwrap() as i8;
+        }
+    }
+    if m.to == CardStack::Side && p.side.get(m.to_num as usize).is_some_and(|s| s.is_empty()) {
+        m.to_num = p.side.iter().position(|s| s.is_empty()).unwrap() as i8;
+    }
+    Action::Play(m)
+}
+
+// Play one decision of a turn like SkipBoGame::play would, returns true once the turn is over
+pub(crate) fn step(game: &mut SkipBoGame, player_num: i8, action: &Action, policy: &dyn Player) -> bool {
+    let m = match action {
+        Action::Play(m) => *m,
+        Action::EndTurn => {
+            if !game.players[player_num as usize].hand.is_empty() {
+                let m = policy.select_stack(&game.view(player_num));
+                game.discard(player_num, &m).expect("rollout players put valid cards on SIDE");
+            }
+            game.end_turn(player_num, false);
+            return true
+        }
+    };
+
+    if m.to == CardStack::Side {
+        game.discard(player_num, &m).expect("searched moves are valid");
+        game.end_turn(player_num, false);
+        return true
+    }
+
+    game.execute_move(player_num, &m).expect("searched moves are valid");
+    if game.check_win() {
+        return true
+    }
+
+    // Refill HAND once it is empty, if every card is in play the turn is over
+    if game.players[player_num as usize].hand.is_empty() {
+        game.refill_hand(player_num);
+    }
+    if game.players[player_num as usize].hand.is_empty() && game.get_valid_moves(player_num).is_empty() {
+        game.end_turn(player_num, false);
+        return true
+    }
+
+    false
+}
+
+// The decision the rollout player would make in the searched state
+pub(crate) fn suggested_action(game: &SkipBoGame, view: &GameView, discard_only: bool, policy: &dyn Player) -> Action {
+    if discard_only {
+        return Action::Play(policy.select_stack(view))
+    }
+    policy.select_move(&game.get_valid_moves(view.player_num), view).map_or(Action::EndTurn, Action::Play)
+}
+
+// Pick the root action with the best average value from its visits and summed values. The suggested action is credited with prior wins,
+// so the search only deviates from it if another action is clearly better. Rated positions differ by little, so the visits of UCT stay
+// close together and the average is a better guide than the most visited action
+pub(crate) fn choose_action(game: &SkipBoGame, player_num: i8, actions: &[Action], mut stats: Vec<(u32, f64)>, suggested: Action, prior: u32, rng: &mut StdRng) -> Option<Action> {
+    if let Some(index) = actions.iter().position(|a| *a == canonical_action(game, player_num, suggested)) {
+        stats[index].0 += prior;
+        stats[index].1 += prior as f64;
+    }
+
+    let mean = |i: usize| if stats[i].0 == 0 { f64::NEG_INFINITY } else { stats[i].1 / stats[i].0 as f64 };
+    let mut order: Vec<usize> = (0..actions.len()).collect();
+    order.shuffle(rng);
+    order.into_iter().max_by(|a, b| mean(*a).total_cmp(&mean(*b))).map(|i| actions[i])
+}
+
+// Finish the searched turn if it is still going on and play the following turns, then rate the game
+fn simulate(game: &mut SkipBoGame, player_num: i8, turn_over: bool, depth: usize, policy: &dyn Player, evaluator: &dyn Evaluator) -> f64 {
+    if !turn_over {
+        game.continue_turn(player_num, policy);
+        game.end_turn(player_num, false);
+    }
+
+    let player_count = game.players.len();
+    for t in 0..depth {
+        if game.check_win() {
+            break;
+        }
+        let next = (player_num as usize + 1 + t) % player_count;
+        game.play(next as i8, policy);
+    }
+
+    rate(game, player_num, evaluator)
+}
+
+// Result of a simulated game for the player's team: 1 for a win, 0 for a loss and 0.5 for a draw. An unfinished game is rated by the evaluator
+// compared to the best player of another team
+fn rate(game: &mut SkipBoGame, player_num: i8, evaluator: &dyn Evaluator) -> f64 {
+    let team = game.teams[player_num as usize];
+    if game.check_win() {
+        return match game.winner {
+            -1 => 0.5,
+            w if game.teams[w as usize] == team => 1.0,
+            _ => 0.0
+        }
+    }
+
+    let own = evaluator.evaluate(game, player_num);
+    let other = (0..game.players.len() as i8).filter(|q| game.teams[*q as usize] != team).map(|q| evaluator.evaluate(game, q)).fold(f64::NEG_INFINITY, f64::max);
+
+    0.5 + 0.5 * (own - other) / (own.abs() + other.abs()).max(1.0)
+}
+
+// Searches the moves of its turn with Monte Carlo Tree Search (UCT). The cards it cannot see are dealt at random several times per decision
+// and every deal is searched with its own tree, every iteration plays the turn on to its end and the following turns with the rollout players.
+// The rollouts assume the player goes back to the rollout player's moves afterwards, so holding cards back looks better than it is
+// when done every turn. The rollout player's choice is kept unless the search clearly prefers another move
+pub struct MctsPlayer {
+    pub search: SearchConfig,
+    // Number of deals of the hidden cards the iterations are split over
+    pub determinizations: usize,
+    rng: RefCell<StdRng>
+}
+
+impl MctsPlayer {
+    pub fn new(seed: u64) -> Self {
+        MctsPlayer {
+            search: SearchConfig::default(),
+            determinizations: 10,
+            rng: RefCell::new(StdRng::seed_from_u64(seed))
+        }
+    }
+
+    fn uct(&self, node: &Node, parent_visits: u32) -> f64 {
+        self.search.uct(node.value, node.visits, parent_visits)
+    }
+
+    // Run UCT on one determinization, returns the visits and summed value of every move of the root
+    fn search_tree(&self, root_game: &SkipBoGame, player_num: i8, actions: &[Action], budget: Budget, policy: &dyn Player, rng: &mut StdRng) -> Vec<(u32, f64)> {
+        let mut nodes = vec![Node::new(None, None, actions.to_vec())];
+        let mut iteration = 0;
+        while budget.allows(iteration) {
+            iteration += 1;
+            let mut game = root_game.clone();
+            let mut node = 0;
+            let mut turn_over = false;
+
+            // Selection: follow the best UCT child while every move of a node has been tried
+            while nodes[node].untried.is_empty() && !nodes[node].children.is_empty() {
+                let parent_visits = nodes[node].visits;
+                node = *nodes[node].children.iter().max_by(|a, b| self.uct(&nodes[**a], parent_visits).total_cmp(&self.uct(&nodes[**b], parent_visits))).unwrap();
+                turn_over = step(&mut game, player_num, &nodes[node].action.unwrap(), policy);
+            }
+
+            // Expansion: try one new move
+            if !nodes[node].untried.is_empty() {
+                let index = rng.gen_range(0..nodes[node].untried.len());
+                let action = nodes[node].untried.swap_remove(index);
+                turn_over = step(&mut game, player_num, &action, policy);
+
+                let untried = if turn_over { Vec::new() } else { turn_actions(&game, player_num, false) };
+                nodes.push(Node::new(Some(node), Some(action), untried));
+                let child = nodes.len() - 1;
+                nodes[node].children.push(child);
+                node = child;
+            }
+
+            // Rollout and backpropagation
+            let value = self.search.simulate(&mut game, player_num, turn_over, policy);
+            let mut current = Some(node);
+            while let Some(n) = current {
+                nodes[n].visits += 1;
+                nodes[n].value += value;
+                current = nodes[n].parent;
+            }
+        }
+
+        actions.iter().map(|a| {
+            nodes[0].children.iter().find(|c| nodes[**c].action == Some(*a)).map_or((0, 0.0), |c| (nodes[*c].visits, nodes[*c].value))
+        }).collect()
+    }
+
+    fn find_action(&self, view: &GameView, discard_only: bool) -> Option<Action> {
+        let mut rng = self.rng.borrow_mut();
+        let player_num = view.player_num;
+        let policy = self.search.rollout.player(&mut rng);
+
+        // The moves of the root only depend on the visible cards, they are the same in every determinization
+        let hidden = HiddenCards::sample(view, &mut *rng);
+        let mut root_game = SkipBoGame::from_view(view, hidden, rng.gen());
+        let actions = turn_actions(&root_game, player_num, discard_only);
+        if actions.len() <= 1 {
+            return actions.first().copied();
+        }
+
+        // Split the budget over the determinizations and add up the statistics of the root moves
+        let determinizations = self.determinizations.max(1);
+        let start = Instant::now();
+        let mut stats = vec![(0, 0.0); actions.len()];
+        for d in 0..determinizations {
+            if d > 0 {
+                root_game = SkipBoGame::from_view(view, HiddenCards::sample(view, &mut *rng), rng.gen());
+            }
+            let budget = match self.search.time_limit {
+                Some(limit) => Budget::Until(start + limit * (d + 1) as u32 / determinizations as u32),
+                None => Budget::Iterations(self.search.iterations * (d + 1) / determinizations - self.search.iterations * d / determinizations)
+            };
+            for (total, (visits, value)) in stats.iter_mut().zip(self.search_tree(&root_game, player_num, &actions, budget, policy.as_ref(), &mut rng)) {
+                total.0 += visits;
+                total.1 += value;
+            }
+        }
+
+        let suggested = suggested_action(&root_game, view, discard_only, policy.as_ref());
+        choose_action(&root_game, player_num, &actions, stats, suggested, self.search.prior, &mut rng)
+    }
+}
+
+impl Player for MctsPlayer {
+    fn name(&self) -> String {
+        "mcts".to_string()
+    }
+
+    fn select_move(&self, _moves: &[Move], view: &GameView) -> Option<Move> {
+        field_move(self.find_action(view, false))
+    }
+
+    fn select_stack(&self, view: &GameView) -> Move {
+        side_move(self.find_action(view, true))
+    }
+}
@@ -1,3 +1,10 @@
 pub mod good_player;
 pub mod simple_player;
-pub mod player;
\ No newline at end of file
+pub mod bad_player;
+pub mod random_player;
+pub mod mcts_player;
+pub mod ismcts_player;
+pub mod turn_planner;
+pub mod player;
+pub mod human_player;
+pub mod registry;
\ No newline at end of file
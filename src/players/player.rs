@@ -1,38 +1,35 @@
-use crate::move_stack::Move;
-use rand::{Rng};
+use crate::{move_stack::{Move, MoveError}, card::Card, game_view::GameView};
 
 pub trait NewPlayerState {
-    fn new(stack_size: i32) -> Self;
+    fn new(stack: Vec<Card>, side_piles: usize) -> Self;
 }
 
-#[derive(Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PlayerState {
-    pub hand: Vec<i8>,
-    pub side: [Vec<i8>; 4],
-    pub stack: Vec<i8>
+    pub hand: Vec<Card>,
+    pub side: Vec<Vec<Card>>,
+    pub stack: Vec<Card>,
+    // Set if the player was disqualified for trying an illegal move
+    pub forfeited: Option<MoveError>
 }
 
 impl NewPlayerState for PlayerState {
-    fn new(stack_size: i32) -> Self {
-        let mut rng = rand::thread_rng();
-
-        let mut stack: Vec<i8> = Vec::new();
-        for _ in 0..stack_size {
-            let mut random_num = rng.gen_range(1..14);
-            if random_num == 13 { random_num = -1 };
-
-            stack.push(random_num);
-        }
-
+    // The STACK is dealt from the deck, the HAND is drawn at the start of the player's first turn
+    fn new(stack: Vec<Card>, side_piles: usize) -> Self {
         PlayerState { 
-            hand: vec![rng.gen_range(1..13), rng.gen_range(1..13), rng.gen_range(1..13), rng.gen_range(1..13), rng.gen_range(1..13)], 
-            side: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
-            stack
+            hand: Vec::new(), 
+            side: vec![Vec::new(); side_piles],
+            stack,
+            forfeited: None
         }
     }
 }
 
 pub trait Player {
-    fn select_move(&self, moves: Vec<Move>, stack: i8, opponent_stack: i8, side: [Vec<i8>; 4], hand: Vec<i8>, playing_field: [(i8, bool); 4]) -> Option<Move>;
-    fn select_stack(&self, hand: Vec<i8>, side: [std::vec::Vec<i8>; 4]) -> Move;
+    // Name of the strategy, used to identify the player in game records
+    fn name(&self) -> String;
+    // Select one of the valid moves to play, None ends the turn
+    fn select_move(&self, moves: &[Move], view: &GameView) -> Option<Move>;
+    // Select the card to put from HAND on SIDE, which ends the turn
+    fn select_stack(&self, view: &GameView) -> Move;
 }
\ No newline at end of file
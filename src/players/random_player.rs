@@ -0,0 +1,38 @@
+use std::cell::RefCell;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{players::player::Player, move_stack::{Move, CardStack}, game_view::GameView};
+
+pub struct RandomPlayer {
+    rng: RefCell<StdRng>
+}
+
+impl RandomPlayer {
+    // The same seed makes the same choices in the same situations
+    pub fn new(seed: u64) -> Self {
+        RandomPlayer { rng: RefCell::new(StdRng::seed_from_u64(seed)) }
+    }
+}
+
+// Plays a random valid move or ends the turn, every choice is equally likely. Puts a random card on a random SIDE stack
+impl Player for RandomPlayer {
+    fn name(&self) -> String {
+        "random".to_string()
+    }
+
+    fn select_move(&self, moves: &[Move], _view: &GameView) -> Option<Move> {
+        // One more choice than there are moves, which ends the turn
+        moves.get(self.rng.borrow_mut().gen_range(0..=moves.len())).copied()
+    }
+
+    fn select_stack(&self, view: &GameView) -> Move {
+        let mut rng = self.rng.borrow_mut();
+        Move {
+            from: CardStack::Hand,
+            from_num: rng.gen_range(0..view.hand.len().max(1)) as i8,
+            to: CardStack::Side,
+            to_num: rng.gen_range(0..view.side.len()) as i8
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use std::{collections::BTreeMap, fmt, str::FromStr, time::Duration};
+
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+
+use crate::{evaluation::{LinearEvaluator, Features}, players::{player::Player, simple_player::SimplePlayer, good_player::GoodPlayer, bad_player::BadPlayer, random_player::RandomPlayer, mcts_player::{MctsPlayer, SearchConfig}, ismcts_player::IsmctsPlayer, turn_planner::TurnPlanner}};
+
+// A player's name and the parameters it is built with.
+// Ex: "good" or "random:seed=7"
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PlayerSpec {
+    pub name: String,
+    pub params: BTreeMap<String, String>
+}
+
+impl PlayerSpec {
+    // Read a parameter, the default is used if it is missing
+    pub fn param<T: FromStr>(&self, key: &str, default: T) -> Result<T, RegistryError> {
+        match self.params.get(key) {
+            None => Ok(default),
+            Some(value) => value.parse().map_err(|_| RegistryError::InvalidParameter { player: self.name.clone(), key: key.to_string(), value: value.clone() })
+        }
+    }
+
+    // The LinearEvaluator with the weights from the file given as parameter "weights", or the default. A feature given as
+    // parameter replaces its weight, ex: "planner:wilds=0.5"
+    pub fn evaluator(&self, default: LinearEvaluator) -> Result<LinearEvaluator, RegistryError> {
+        let evaluator = match self.params.get("weights") {
+            None => default,
+            Some(path) => LinearEvaluator::load(path).map_err(|e| RegistryError::InvalidWeights { player: self.name.clone(), error: e.to_string() })?
+        };
+
+        let mut weights = evaluator.weights.to_vec();
+        for (weight, name) in weights.iter_mut().zip(Features::NAMES) {
+            *weight = self.param(name, *weight)?;
+        }
+        Ok(LinearEvaluator::new(Features::from_slice(&weights)))
+    }
+
+    // The parameters of a player with an evaluator: the given ones, "weights" and one per feature
+    pub fn evaluator_params<'a>(keys: &[&'a str]) -> Vec<&'a str> {
+        [keys, &["weights"], &Features::NAMES].concat()
+    }
+
+    // The search settings of a tree search player. The time limit per decision is given in milliseconds as "time", it
+    // replaces the iterations. The evaluator counts the cards left unless the spec gives weights
+    pub fn search_config(&self) -> Result<SearchConfig, RegistryError> {
+        let config = SearchConfig::default();
+        Ok(SearchConfig {
+            iterations: self.param("iterations", config.iterations)?,
+            time_limit: match self.params.contains_key("time") {
+                true => Some(Duration::from_millis(self.param("time", 0)?)),
+                false => config.time_limit
+            },
+            exploration: self.param("exploration", config.exploration)?,
+            rollout: self.param("rollout", config.rollout)?,
+            depth: self.param("depth", config.depth)?,
+            prior: self.param("prior", config.prior)?,
+            evaluator: Box::new(self.evaluator(LinearEvaluator::cards_left())?)
+        })
+    }
+
+    // The parameters of a tree search player: the given ones, those of its SearchConfig and those of its evaluator
+    pub fn search_params<'a>(keys: &[&'a str]) -> Vec<&'a str> {
+        PlayerSpec::evaluator_params(&[keys, &SearchConfig::PARAMS].concat())
+    }
+
+    // Reject parameters the player does not know, so a typo does not silently fall back to the default
+    pub fn allow_params(&self, keys: &[&str]) -> Result<(), RegistryError> {
+        match self.params.keys().find(|k| !keys.contains(&k.as_str())) {
+            None => Ok(()),
+            Some(key) => Err(RegistryError::UnknownParameter { player: self.name.clone(), key: key.clone() })
+        }
+    }
+}
+
+impl fmt::Display for PlayerSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        for (key, value) in &self.params {
+            write!(f, ":{}={}", key, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PlayerSpec {
+    type Err = RegistryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split(':');
+        let name = parts.next().unwrap_or("").to_string();
+        if name.is_empty() {
+            return Err(RegistryError::InvalidSpec(s.to_string()));
+        }
+
+        let mut params = BTreeMap::new();
+        for param in parts {
+            let (key, value) = param.split_once('=').ok_or(RegistryError::InvalidSpec(s.to_string()))?;
+            params.insert(key.to_string(), value.to_string());
+        }
+
+        Ok(PlayerSpec { name, params })
+    }
+}
+
+// Specs are stored as their string in config files
+impl Serialize for PlayerSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PlayerSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RegistryError {
+    // The spec is not of the form "name:key=value:key=value"
+    InvalidSpec(String),
+    UnknownPlayer(String),
+    UnknownParameter { player: String, key: String },
+    InvalidParameter { player: String, key: String, value: String },
+    // The file given as parameter "weights" could not be loaded
+    InvalidWeights { player: String, error: String }
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::InvalidSpec(spec) => write!(f, "invalid player \"{}\", expected \"name:key=value\"", spec),
+            RegistryError::UnknownPlayer(name) => write!(f, "unknown player \"{}\"", name),
+            RegistryError::UnknownParameter { player, key } => write!(f, "player \"{}\" has no parameter \"{}\"", player, key),
+            RegistryError::InvalidParameter { player, key, value } => write!(f, "invalid value \"{}\" for parameter \"{}\" of player \"{}\"", value, key, player),
+            RegistryError::InvalidWeights { player, error } => write!(f, "player \"{}\": {}", player, error)
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+// Builds a player from the parameters of its spec. A player that decides at random is seeded with the given seed, unless
+// its spec sets one
+pub type PlayerFactory = Box<dyn Fn(&PlayerSpec, u64) -> Result<Box<dyn Player>, RegistryError> + Send + Sync>;
+
+// Maps player names to the factories that build them
+pub struct PlayerRegistry {
+    factories: BTreeMap<String, PlayerFactory>
+}
+
+impl PlayerRegistry {
+    // A registry without any players
+    pub fn empty() -> Self {
+        PlayerRegistry { factories: BTreeMap::new() }
+    }
+
+    // A registry with every player of this crate
+    pub fn new() -> Self {
+        let mut registry = PlayerRegistry::empty();
+
+        registry.register("simple", |spec, _| {
+            spec.allow_params(&[])?;
+            Ok(Box::new(SimplePlayer {}))
+        });
+        registry.register("good", |spec, _| {
+            spec.allow_params(&["help_distance", "block_distance"])?;
+            let player = GoodPlayer::default();
+            Ok(Box::new(GoodPlayer {
+                help_distance: spec.param("help_distance", player.help_distance)?,
+                block_distance: spec.param("block_distance", player.block_distance)?
+            }))
+        });
+        registry.register("bad", |spec, _| {
+            spec.allow_params(&[])?;
+            Ok(Box::new(BadPlayer {}))
+        });
+        registry.register("random", |spec, seed| {
+            spec.allow_params(&["seed"])?;
+            Ok(Box::new(RandomPlayer::new(spec.param("seed", seed)?)))
+        });
+        registry.register("mcts", |spec, seed| {
+            spec.allow_params(&PlayerSpec::search_params(&["determinizations", "seed"]))?;
+            let mut player = MctsPlayer::new(spec.param("seed", seed)?);
+            player.search = spec.search_config()?;
+            player.determinizations = spec.param("determinizations", player.determinizations)?;
+            Ok(Box::new(player))
+        });
+        registry.register("planner", |spec, seed| {
+            spec.allow_params(&PlayerSpec::evaluator_params(&["seed"]))?;
+            Ok(Box::new(TurnPlanner::with_evaluator(Box::new(spec.evaluator(LinearEvaluator::default())?), spec.param("seed", seed)?)))
+        });
+        registry.register("ismcts", |spec, seed| {
+            spec.allow_params(&PlayerSpec::search_params(&["seed"]))?;
+            let mut player = IsmctsPlayer::new(spec.param("seed", seed)?);
+            player.search = spec.search_config()?;
+            Ok(Box::new(player))
+        });
+
+        registry
+    }
+
+    // Add a player, a player that is already registered under that name is replaced
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where F: Fn(&PlayerSpec, u64) -> Result<Box<dyn Player>, RegistryError> + Send + Sync + 'static {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    // Names of the registered players in alphabetical order
+    pub fn names(&self) -> Vec<&str> {
+        self.factories.keys().map(|n| n.as_str()).collect()
+    }
+
+    // Build the player, a player that decides at random and has no seed in its spec gets the given one. Derive it from the
+    // seed of the games, so they can be repeated
+    pub fn build(&self, spec: &PlayerSpec, seed: u64) -> Result<Box<dyn Player>, RegistryError> {
+        let factory = self.factories.get(&spec.name).ok_or(RegistryError::UnknownPlayer(spec.name.clone()))?;
+        factory(spec, seed)
+    }
+
+    // Parse a spec and build the player with a random seed, ex: "random:seed=7"
+    pub fn create(&self, spec: &str) -> Result<Box<dyn Player>, RegistryError> {
+        self.build(&spec.parse()?, rand::random())
+    }
+}
+
+impl Default for PlayerRegistry {
+    fn default() -> Self {
+        PlayerRegistry::new()
+    }
+}
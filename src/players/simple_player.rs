@@ -1,17 +1,21 @@
 use std::cmp::Ordering;
 
-use crate::{players::player::Player, move_stack::{Move, CardStack}};
+use crate::{players::player::Player, move_stack::{Move, CardStack}, game_view::GameView};
 
 pub struct SimplePlayer {}
 // Always plays the first valid_move (mostly from stack to playing_field) and puts the first card to the first SIDE stack
 impl Player for SimplePlayer {
-    fn select_move(&self, moves: Vec<Move>, _stack: i8, _opponent_stack: i8, _side: [Vec<i8>; 4], _hand: Vec<i8>, _playing_field: [(i8, bool); 4]) -> Option<Move> {
+    fn name(&self) -> String {
+        "simple".to_string()
+    }
+
+    fn select_move(&self, moves: &[Move], _view: &GameView) -> Option<Move> {
         let m = moves.first().unwrap();
         Some(Move { from: m.from, from_num: m.from_num, to: m.to, to_num: m.to_num })
     }
 
-    fn select_stack(&self, _hand: Vec<i8>, side: [std::vec::Vec<i8>; 4]) -> Move {
-        let index_of_min: usize = side
+    fn select_stack(&self, view: &GameView) -> Move {
+        let index_of_min: usize = view.side
             .iter()
             .enumerate()
             .min_by(|(_, a), (_, b)| a.len().partial_cmp(&b.len()).unwrap_or(Ordering::Equal))
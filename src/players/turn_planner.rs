@@ -0,0 +1,140 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{game::{Game, SkipBoGame}, players::{player::Player, mcts_player::{Action, turn_actions}}, move_stack::{Move, CardStack}, card::Card, game_view::GameView, determinization::HiddenCards, evaluation::{Evaluator, LinearEvaluator}};
+
+// A planned turn: the moves to FIELD with the HAND they are played from and the card put on SIDE at the end.
+// Without a card for SIDE the turn goes on with new cards and is planned again
+struct Plan {
+    turn: i32,
+    moves: Vec<(Move, Vec<Card>)>,
+    discard: Option<(Move, Vec<Card>)>
+}
+
+// Every player's SIDE and STACK size of the team, HAND as a set of cards and FIELD as a set of stacks
+type StateKey = (Vec<Card>, Vec<Vec<Card>>, Vec<usize>, Vec<(Option<u8>, bool, usize)>);
+
+// Plans whole turns instead of one card at a time: searches every order of moves to FIELD depth first, skips states already
+// reached in another order and rates every state the turn can end in, including each card that can be put on SIDE.
+// Playing a STACK card or the last card of HAND shows new cards, so the plan ends there and the rest of the turn is planned again
+pub struct TurnPlanner {
+    // Rates the states the turn can end in
+    pub evaluator: Box<dyn Evaluator>,
+    plan: RefCell<Option<Plan>>,
+    rng: RefCell<StdRng>
+}
+
+impl TurnPlanner {
+    pub fn new(seed: u64) -> Self {
+        TurnPlanner::with_evaluator(Box::new(LinearEvaluator::default()), seed)
+    }
+
+    pub fn with_evaluator(evaluator: Box<dyn Evaluator>, seed: u64) -> Self {
+        TurnPlanner { evaluator, plan: RefCell::new(None), rng: RefCell::new(StdRng::seed_from_u64(seed)) }
+    }
+
+    fn state_key(game: &SkipBoGame, player_num: i8) -> StateKey {
+        let mut hand = game.players[player_num as usize].hand.clone();
+        hand.sort_by_key(|c| c.value());
+        let team: Vec<usize> = (0..game.players.len()).filter(|q| *q == player_num as usize || game.is_partner(player_num, *q as i8)).collect();
+        let mut field: Vec<(Option<u8>, bool, usize)> = game.playing_field.iter().map(|f| (f.top_value(), f.wild_on_top(), f.cards.len())).collect();
+        field.sort();
+
+        (hand, team.iter().flat_map(|q| game.players[*q].side.clone()).collect(), team.iter().map(|q| game.players[*q].stack.len()).collect(), field)
+    }
+
+    // Keep the plan if it is rated better than the best one so far
+    fn consider(&self, best: &mut Option<(f64, Plan)>, score: f64, plan: Plan) {
+        if best.as_ref().is_none_or(|(s, _)| score > *s) {
+            *best = Some((score, plan));
+        }
+    }
+
+    fn explore(&self, game: &mut SkipBoGame, player_num: i8, path: &mut Vec<(Move, Vec<Card>)>, seen: &mut HashSet<StateKey>, best: &mut Option<(f64, Plan)>) {
+        if !seen.insert(TurnPlanner::state_key(game, player_num)) {
+            return;
+        }
+
+        // A HAND that was played empty is refilled and a STACK shows its next card, the plan stops there. Without cards in HAND
+        // at the start nothing is left to draw and the turn can end without a card for SIDE
+        let hand_empty = game.players[player_num as usize].hand.is_empty();
+        let stack_played = path.last().is_some_and(|(m, _)| matches!(m.from, CardStack::Stack | CardStack::PartnerStack(_)));
+        if game.check_win() || stack_played || hand_empty {
+            let score = self.evaluator.evaluate(game, player_num);
+            self.consider(best, score, Plan { turn: game.turn, moves: path.clone(), discard: None });
+            if !path.is_empty() || !hand_empty {
+                return;
+            }
+        }
+
+        // End the turn here with every card that can be put on SIDE
+        let hand = game.players[player_num as usize].hand.clone();
+        for action in turn_actions(game, player_num, true) {
+            let Action::Play(m) = action else { continue };
+            game.discard(player_num, &m).expect("planned moves are valid");
+            let score = self.evaluator.evaluate(game, player_num);
+            game.undo();
+            self.consider(best, score, Plan { turn: game.turn, moves: path.clone(), discard: Some((m, hand.clone())) });
+        }
+
+        // Or play on
+        for action in turn_actions(game, player_num, false) {
+            let Action::Play(m) = action else { continue };
+            game.execute_move(player_num, &m).expect("planned moves are valid");
+            path.push((m, hand.clone()));
+            self.explore(game, player_num, path, seen, best);
+            path.pop();
+            game.undo();
+        }
+    }
+
+    fn make_plan(&self, view: &GameView) -> Plan {
+        let mut rng = self.rng.borrow_mut();
+        let mut game = SkipBoGame::from_view(view, HiddenCards::sample(view, &mut *rng), rng.gen());
+
+        let mut best = None;
+        self.explore(&mut game, view.player_num, &mut Vec::new(), &mut HashSet::new(), &mut best);
+        best.map(|(_, plan)| plan).expect("every state can end the turn")
+    }
+}
+
+impl Player for TurnPlanner {
+    fn name(&self) -> String {
+        "planner".to_string()
+    }
+
+    fn select_move(&self, moves: &[Move], view: &GameView) -> Option<Move> {
+        let mut plan = self.plan.borrow_mut();
+
+        // Plan again at the start of a turn, once the plan ran out of moves without a card for SIDE or if the cards differ from the plan
+        let up_to_date = |p: &Plan| p.turn == view.turn && match p.moves.first() {
+            Some((m, hand)) => hand == view.hand && moves.contains(m),
+            None => p.discard.as_ref().is_some_and(|(_, hand)| hand == view.hand)
+        };
+        if !plan.as_ref().is_some_and(up_to_date) {
+            *plan = Some(self.make_plan(view));
+        }
+
+        let p = plan.as_mut().unwrap();
+        if p.moves.is_empty() {
+            return None
+        }
+        Some(p.moves.remove(0).0)
+    }
+
+    fn select_stack(&self, view: &GameView) -> Move {
+        let mut plan = self.plan.borrow_mut();
+        let planned = plan.as_ref().filter(|p| p.turn == view.turn && p.moves.is_empty()).and_then(|p| p.discard.as_ref()).filter(|(_, hand)| hand == view.hand);
+        if let Some((m, _)) = planned {
+            let m = *m;
+            *plan = None;
+            return m
+        }
+
+        // No move to FIELD was possible, so only the card for SIDE is chosen
+        let new_plan = self.make_plan(view);
+        *plan = None;
+        new_plan.discard.map_or(Move { from: CardStack::Hand, from_num: 0, to: CardStack::Side, to_num: 0 }, |(m, _)| m)
+    }
+}
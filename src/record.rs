@@ -0,0 +1,338 @@
+use std::{fmt, fs, io, str::FromStr};
+
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+
+use crate::{game::{Game, SkipBoGame}, deck::new_deck, history::GameEvent, move_stack::{Move, CardStack, MoveError}, ruleset::{Ruleset, MIN_PLAYERS, MAX_PLAYERS}};
+
+// The moves a player made during one of his turns. If he tried an illegal move and forfeited, that move is kept as well
+#[derive(Debug, PartialEq, Clone)]
+pub struct TurnRecord {
+    pub player_num: i8,
+    pub moves: Vec<Move>,
+    pub forfeit: Option<Move>
+}
+
+// Compact notation of a turn: the player's number followed by his moves, an illegal move is marked with "!"
+// Ex: "1: S>F2 H3>F0 H1>D2"
+impl fmt::Display for TurnRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:", self.player_num)?;
+        for m in &self.moves {
+            write!(f, " {}", m)?;
+        }
+        if let Some(m) = &self.forfeit {
+            write!(f, " !{}", m)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TurnRecord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (player_num, moves) = s.split_once(':').ok_or(format!("missing ':' in turn \"{}\"", s))?;
+        let player_num = player_num.trim().parse::<i8>().map_err(|_| format!("invalid player number in turn \"{}\"", s))?;
+
+        let mut turn = TurnRecord { player_num, moves: Vec::new(), forfeit: None };
+        for m in moves.split_whitespace() {
+            match m.strip_prefix('!') {
+                Some(illegal) => turn.forfeit = Some(illegal.parse()?),
+                None => turn.moves.push(m.parse()?)
+            }
+        }
+
+        Ok(turn)
+    }
+}
+
+// Turns are stored in their compact notation
+impl Serialize for TurnRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TurnRecord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    // The record has fewer or more players than a game can seat
+    PlayerCount(usize),
+    // The ruleset of the record cannot be dealt or played, the reason is given
+    InvalidRuleset(String),
+    // The record does not have one team number for every player
+    TeamMismatch { players: usize, teams: usize },
+    // A turn of the record was played by a player the game does not have
+    UnknownPlayer { turn: usize, player_num: i8 },
+    // A move of the record was rejected by the game
+    IllegalMove { turn: usize, error: MoveError },
+    // The replayed game did not end with the recorded winner
+    ResultMismatch { expected: i8, found: i8 }
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Io(error) => write!(f, "cannot access game record: {}", error),
+            RecordError::Json(error) => write!(f, "invalid game record: {}", error),
+            RecordError::PlayerCount(players) => write!(f, "a game has {} to {} players, the record has {}", MIN_PLAYERS, MAX_PLAYERS, players),
+            RecordError::InvalidRuleset(reason) => write!(f, "invalid ruleset: {}", reason),
+            RecordError::TeamMismatch { players, teams } => write!(f, "the record has {} players but {} team numbers", players, teams),
+            RecordError::UnknownPlayer { turn, player_num } => write!(f, "turn {} is played by player {}, who is not in the game", turn, player_num),
+            RecordError::IllegalMove { turn, error } => write!(f, "illegal move in turn {}: {}", turn, error),
+            RecordError::ResultMismatch { expected, found } => write!(f, "replay was won by player {} instead of player {}", found, expected)
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl From<io::Error> for RecordError {
+    fn from(error: io::Error) -> Self {
+        RecordError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for RecordError {
+    fn from(error: serde_json::Error) -> Self {
+        RecordError::Json(error)
+    }
+}
+
+// Everything needed to replay a game: the ruleset, the seed the cards were shuffled with, the names and teams of the players and every turn
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub ruleset: Ruleset,
+    pub seed: u64,
+    pub players: Vec<String>,
+    // Missing in records from before team play, every player is his own team then
+    #[serde(default)]
+    pub teams: Vec<i8>,
+    pub turns: Vec<TurnRecord>,
+    // -1 if the game ended in a draw
+    pub winner: i8
+}
+
+impl GameRecord {
+    // Collect the finished turns from the history of a game
+    pub fn from_game(game: &SkipBoGame, players: Vec<String>) -> Self {
+        let mut turns: Vec<TurnRecord> = Vec::new();
+        let mut moves: Vec<Move> = Vec::new();
+        let mut forfeit: Option<Move> = None;
+
+        for event in &game.history {
+            match event {
+                GameEvent::MovePlayed { m, .. } => moves.push(*m),
+                GameEvent::Forfeited { m, .. } => forfeit = Some(*m),
+                GameEvent::TurnEnded { player_num, .. } => {
+                    turns.push(TurnRecord { player_num: *player_num, moves: std::mem::take(&mut moves), forfeit: forfeit.take() });
+                }
+                _ => {}
+            }
+        }
+
+        GameRecord { ruleset: game.ruleset, seed: game.seed, players, teams: game.teams.clone(), turns, winner: game.winner }
+    }
+
+    // Every turn in compact notation, one turn per line
+    pub fn notation(&self) -> String {
+        self.turns.iter().map(|t| t.to_string()).collect::<Vec<String>>().join("\n")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("game record can be serialized")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, RecordError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), RecordError> {
+        Ok(fs::write(path, self.to_json())?)
+    }
+
+    pub fn load(path: &str) -> Result<Self, RecordError> {
+        GameRecord::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+// Check that the cards of a record's ruleset can be dealt to the players, a hand-edited record could ask for more cards than
+// the deck has or for no piles at all
+fn check_ruleset(ruleset: &Ruleset, players: usize) -> Result<(), RecordError> {
+    let sizes = [("stack_size", ruleset.stack_size), ("hand_size", ruleset.hand_size), ("building_piles", ruleset.building_piles), ("side_piles", ruleset.side_piles), ("highest_card", ruleset.highest_card as usize)];
+    if let Some((name, _)) = sizes.iter().find(|(_, size)| *size == 0) {
+        return Err(RecordError::InvalidRuleset(format!("{} cannot be 0", name)));
+    }
+
+    let deck = new_deck(ruleset).len();
+    if ruleset.stack_size * players > deck {
+        return Err(RecordError::InvalidRuleset(format!("a deck of {} cards cannot deal {} stacks of {} cards", deck, players, ruleset.stack_size)));
+    }
+    Ok(())
+}
+
+// Play a recorded game again through execute_move and check that it ends with the recorded winner
+pub fn replay(record: &GameRecord) -> Result<SkipBoGame, RecordError> {
+    let players = record.players.len();
+    if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&players) {
+        return Err(RecordError::PlayerCount(players));
+    }
+    check_ruleset(&record.ruleset, players)?;
+    if !record.teams.is_empty() && record.teams.len() != players {
+        return Err(RecordError::TeamMismatch { players, teams: record.teams.len() });
+    }
+
+    let mut game = if record.teams.is_empty() {
+        SkipBoGame::with_seed(record.ruleset, record.players.len() as i8, record.seed)
+    } else {
+        SkipBoGame::with_teams(record.ruleset, record.teams.clone(), record.seed)
+    };
+
+    for (index, turn) in record.turns.iter().enumerate() {
+        let p = turn.player_num;
+        if !(0..players as i8).contains(&p) {
+            return Err(RecordError::UnknownPlayer { turn: index, player_num: p });
+        }
+
+        // A player that forfeited does not play anymore
+        if game.players[p as usize].forfeited.is_some() {
+            game.end_turn(p, true);
+            continue;
+        }
+
+        // Refill HAND at the beginning and whenever it is empty while the turn goes on, like SkipBoGame::play
+        game.refill_hand(p);
+
+        let moves_before = game.moves_played;
+        for m in &turn.moves {
+            game.execute_move(p, m).map_err(|error| RecordError::IllegalMove { turn: index, error })?;
+
+            if m.to != CardStack::Side && game.players[p as usize].hand.is_empty() {
+                game.refill_hand(p);
+            }
+        }
+
+        if let Some(m) = &turn.forfeit {
+            // A move that follows the rules can only have been rejected because it should have ended the turn on SIDE
+            let error = game.validate_move(p, m).err().unwrap_or(MoveError::DiscardToField);
+            game.forfeit(p, m, error);
+        }
+
+        game.end_turn(p, game.moves_played == moves_before);
+    }
+
+    game.check_win();
+    if game.winner != record.winner {
+        return Err(RecordError::ResultMismatch { expected: record.winner, found: game.winner });
+    }
+
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game_view::GameView, players::{player::Player, good_player::GoodPlayer, simple_player::SimplePlayer}};
+
+    // Tries to move its STACK card onto SIDE and forfeits
+    struct IllegalPlayer {}
+
+    impl Player for IllegalPlayer {
+        fn name(&self) -> String {
+            "illegal".to_string()
+        }
+
+        fn select_move(&self, _moves: &[Move], _view: &GameView) -> Option<Move> {
+            Some(Move { from: CardStack::Stack, from_num: 0, to: CardStack::Side, to_num: 0 })
+        }
+
+        fn select_stack(&self, _view: &GameView) -> Move {
+            Move { from: CardStack::Hand, from_num: 0, to: CardStack::Side, to_num: 0 }
+        }
+    }
+
+    fn played(game: &mut SkipBoGame, players: Vec<Box<dyn Player>>) -> GameRecord {
+        game.play_out(&players);
+        GameRecord::from_game(game, players.iter().map(|p| p.name()).collect())
+    }
+
+    fn check_round_trip(mut game: SkipBoGame, players: Vec<Box<dyn Player>>, name: &str) -> GameRecord {
+        let record = played(&mut game, players);
+        let path = std::env::temp_dir().join(format!("rust-skipbo-{}-{}.json", name, std::process::id()));
+        let path = path.to_str().unwrap();
+        record.save(path).unwrap();
+        let loaded = GameRecord::load(path);
+        fs::remove_file(path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded, record);
+        let replayed = replay(&loaded).unwrap();
+        assert_eq!(replayed.winner, game.winner);
+        assert_eq!(replayed.history, game.history);
+        record
+    }
+
+    #[test]
+    fn saved_games_replay_to_the_same_end() {
+        for seed in 0..5 {
+            check_round_trip(SkipBoGame::with_seed(Ruleset::official(), 2, seed), vec![Box::new(GoodPlayer::default()), Box::new(SimplePlayer {})], "solo");
+        }
+        check_round_trip(SkipBoGame::with_teams(Ruleset::official(), vec![0, 1, 0, 1], 7), (0..4).map(|_| Box::new(GoodPlayer::default()) as Box<dyn Player>).collect(), "teams");
+        let record = check_round_trip(SkipBoGame::with_seed(Ruleset::quick(), 3, 8), vec![Box::new(IllegalPlayer {}), Box::new(GoodPlayer::default()), Box::new(SimplePlayer {})], "forfeit");
+        assert!(record.turns[0].forfeit.is_some());
+    }
+
+    #[test]
+    fn turns_are_written_in_notation() {
+        let turn: TurnRecord = "1: S>F2 H3>F0 !P2S>D1".parse().unwrap();
+        assert_eq!(turn.player_num, 1);
+        assert_eq!(turn.moves, vec![
+            Move { from: CardStack::Stack, from_num: 0, to: CardStack::Field, to_num: 2 },
+            Move { from: CardStack::Hand, from_num: 3, to: CardStack::Field, to_num: 0 }
+        ]);
+        assert_eq!(turn.forfeit, Some(Move { from: CardStack::PartnerStack(2), from_num: 0, to: CardStack::Side, to_num: 1 }));
+        assert_eq!(turn.to_string(), "1: S>F2 H3>F0 !P2S>D1");
+    }
+
+    #[test]
+    fn broken_records_are_rejected() {
+        let record = played(&mut SkipBoGame::with_seed(Ruleset::official(), 2, 3), vec![Box::new(GoodPlayer::default()), Box::new(SimplePlayer {})]);
+
+        let mut wrong = record.clone();
+        wrong.winner = 1 - record.winner;
+        assert!(matches!(replay(&wrong), Err(RecordError::ResultMismatch { .. })));
+
+        let mut wrong = record.clone();
+        wrong.players.truncate(1);
+        wrong.teams.truncate(1);
+        assert!(matches!(replay(&wrong), Err(RecordError::PlayerCount(1))));
+
+        let mut wrong = record.clone();
+        wrong.ruleset.stack_size = 100;
+        assert!(matches!(replay(&wrong), Err(RecordError::InvalidRuleset(_))));
+
+        let mut wrong = record.clone();
+        wrong.ruleset.side_piles = 0;
+        assert!(matches!(replay(&wrong), Err(RecordError::InvalidRuleset(_))));
+
+        let mut wrong = record.clone();
+        wrong.teams.push(0);
+        assert!(matches!(replay(&wrong), Err(RecordError::TeamMismatch { players: 2, teams: 3 })));
+
+        let mut wrong = record.clone();
+        wrong.turns[1].player_num = 5;
+        assert!(matches!(replay(&wrong), Err(RecordError::UnknownPlayer { turn: 1, player_num: 5 })));
+
+        let mut wrong = record.clone();
+        wrong.turns[0].moves.insert(0, Move { from: CardStack::Side, from_num: 0, to: CardStack::Field, to_num: 0 });
+        assert!(matches!(replay(&wrong), Err(RecordError::IllegalMove { turn: 0, .. })));
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Serialize, Deserialize};
+
+// Number of players a Skip-Bo game can be dealt for
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 6;
+
+// The parameters of a Skip-Bo game that differ between the official rules and common house rules
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct Ruleset {
+    // Number of cards dealt to each player's STACK
+    pub stack_size: usize,
+    // Number of cards a player's HAND is refilled to
+    pub hand_size: usize,
+    // Number of stacks on the FIELD
+    pub building_piles: usize,
+    // Number of SIDE stacks of every player
+    pub side_piles: usize,
+    // Highest card value, a FIELD stack is complete once it reaches this value
+    pub highest_card: u8,
+    // Number of copies of every card value in the deck
+    pub card_copies: usize,
+    // Number of jokers (Skip-Bo cards) in the deck
+    pub jokers: usize,
+    // Instead of being removed, a complete FIELD stack continues at 1
+    pub wrap_around: bool,
+    // A wild may be played onto another wild. Some house rules forbid playing two wilds in a row
+    pub wild_on_wild: bool
+}
+
+impl Ruleset {
+    // Official rules: 162 cards, 4 FIELD stacks that are cleared once they reach 12
+    pub fn official() -> Self {
+        Ruleset {
+            stack_size: 30,
+            hand_size: 5,
+            building_piles: 4,
+            side_piles: 4,
+            highest_card: 12,
+            card_copies: 12,
+            jokers: 18,
+            wrap_around: false,
+            wild_on_wild: true
+        }
+    }
+
+    // FIELD stacks are never cleared, a 1 can be played onto a 12 and a wild cannot be played onto a wild
+    pub fn house_wrap_around() -> Self {
+        Ruleset {
+            wrap_around: true,
+            wild_on_wild: false,
+            ..Ruleset::official()
+        }
+    }
+
+    // Official rules with a short STACK for fast games
+    pub fn quick() -> Self {
+        Ruleset {
+            stack_size: 10,
+            ..Ruleset::official()
+        }
+    }
+
+    // The official rules deal 30 cards to every STACK with 2-4 players and 20 cards with 5-6 players. A shorter STACK is kept
+    pub fn for_players(self, players: usize) -> Self {
+        if players > 4 {
+            Ruleset { stack_size: self.stack_size.min(20), ..self }
+        } else {
+            self
+        }
+    }
+
+    // Look up a preset by its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "official" => Some(Ruleset::official()),
+            "house-wrap-around" => Some(Ruleset::house_wrap_around()),
+            "quick" => Some(Ruleset::quick()),
+            _ => None
+        }
+    }
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset::official()
+    }
+}
@@ -0,0 +1,90 @@
+use std::{num::NonZeroUsize, sync::{Mutex, atomic::{AtomicUsize, Ordering}}, thread};
+
+use rand::{SeedableRng, rngs::StdRng};
+
+// Seed of the RNG stream of one job: the master seed and the job number mixed with SplitMix64, so neighbouring jobs get
+// unrelated streams
+pub fn job_seed(seed: u64, job: usize) -> u64 {
+    let mut z = seed.wrapping_add((job as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE5_E9B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Runs numbered jobs on a pool of threads. Every job gets its own RNG stream derived from the master seed and its number,
+// so the results are the same whatever the number of threads and whichever thread runs a job.
+// Players are not shared between threads, a job builds the players it needs, ex: from their specs
+#[derive(Debug, Clone, Copy)]
+pub struct Runner {
+    pub threads: usize
+}
+
+impl Runner {
+    // One thread per core
+    pub fn new() -> Self {
+        Runner { threads: thread::available_parallelism().map_or(1, NonZeroUsize::get) }
+    }
+
+    pub fn with_threads(threads: usize) -> Self {
+        Runner { threads: threads.max(1) }
+    }
+
+    // Run the jobs 0..jobs and return their results in the order of the jobs. done is called after every finished job,
+    // ex: to advance a progress bar shared by all threads
+    pub fn run<T, F, D>(&self, jobs: usize, seed: u64, job: F, done: D) -> Vec<T>
+    where T: Send, F: Fn(usize, &mut StdRng) -> T + Sync, D: Fn() + Sync {
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<T>>> = Mutex::new((0..jobs).map(|_| None).collect());
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(jobs) {
+                scope.spawn(|| loop {
+                    let n = next.fetch_add(1, Ordering::Relaxed);
+                    if n >= jobs {
+                        break;
+                    }
+
+                    let result = job(n, &mut StdRng::seed_from_u64(job_seed(seed, n)));
+                    results.lock().unwrap()[n] = Some(result);
+                    done();
+                });
+            }
+        });
+
+        results.into_inner().unwrap().into_iter().map(|r| r.expect("every job was run")).collect()
+    }
+}
+
+impl Default for Runner {
+    fn default() -> Self {
+        Runner::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use crate::{game::{Game, SkipBoGame}, players::{player::Player, registry::PlayerRegistry}, ruleset::Ruleset};
+
+    #[test]
+    fn results_do_not_depend_on_the_threads() {
+        let registry = PlayerRegistry::new();
+        let specs = ["random", "good"].map(|s| s.parse().unwrap());
+        let play = |threads: usize| {
+            let finished = AtomicUsize::new(0);
+            let results = Runner::with_threads(threads).run(24, 17, |n, rng| {
+                let players = specs.iter().map(|s| registry.build(s, rng.gen())).collect::<Result<Vec<Box<dyn Player>>, _>>().unwrap();
+                let mut game = SkipBoGame::with_seed(Ruleset::quick(), 2, rng.gen());
+                let rounds = game.play_out(&players);
+                (n, game.winner, rounds, game.history.len())
+            }, || { finished.fetch_add(1, Ordering::Relaxed); });
+            assert_eq!(finished.into_inner(), 24);
+            results
+        };
+
+        let one = play(1);
+        assert_eq!(one.iter().map(|r| r.0).collect::<Vec<_>>(), (0..24).collect::<Vec<_>>());
+        assert_eq!(play(4), one);
+    }
+}
@@ -0,0 +1,251 @@
+use rand::Rng;
+use serde::Serialize;
+
+use crate::{game::{Game, SkipBoGame}, players::{player::Player, registry::{PlayerRegistry, PlayerSpec, RegistryError}}, ruleset::Ruleset, runner::{Runner, job_seed}};
+
+// Outcome of a sequential probability ratio test so far
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Decision {
+    // The LLR lies between the bounds, more games are needed
+    Continue,
+    // The player wins at least as often as the alternative hypothesis says
+    AcceptH1,
+    // The player wins at most as often as the null hypothesis says
+    AcceptH0
+}
+
+// Sequential probability ratio test of the win rate of player A against player B: H0 says A wins with probability p0,
+// H1 with probability p1 > p0. After every game the log-likelihood ratio of H1 to H0 is compared with two bounds, once it
+// leaves them one hypothesis is accepted. alpha is the chance of accepting H1 although H0 holds, beta of accepting H0 although
+// H1 holds. Ex: p0 = 0.5 and p1 = 0.55 tests whether A wins at least 5% more games than B
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct Sprt {
+    pub p0: f64,
+    pub p1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub wins: u64,
+    pub losses: u64
+}
+
+impl Sprt {
+    pub fn new(p0: f64, p1: f64, alpha: f64, beta: f64) -> Self {
+        assert!(0.0 < p0 && p0 < p1 && p1 < 1.0, "the win rates need 0 < p0 < p1 < 1");
+        assert!(alpha > 0.0 && beta > 0.0 && alpha + beta < 1.0, "the error rates need to be positive and below 1 together");
+        Sprt { p0, p1, alpha, beta, wins: 0, losses: 0 }
+    }
+
+    // Whether A won a game against B
+    pub fn record(&mut self, won: bool) {
+        match won {
+            true => self.wins += 1,
+            false => self.losses += 1
+        }
+    }
+
+    pub fn games(&self) -> u64 {
+        self.wins + self.losses
+    }
+
+    // Log-likelihood ratio of H1 to H0 after the games recorded
+    pub fn llr(&self) -> f64 {
+        self.wins as f64 * (self.p1 / self.p0).ln() + self.losses as f64 * ((1.0 - self.p1) / (1.0 - self.p0)).ln()
+    }
+
+    // H0 is accepted once the LLR drops to the lower bound, H1 once it reaches the upper bound
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    pub fn decision(&self) -> Decision {
+        let llr = self.llr();
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            Decision::AcceptH1
+        } else if llr <= lower {
+            Decision::AcceptH0
+        } else {
+            Decision::Continue
+        }
+    }
+}
+
+// The state of the test after some games
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct SprtPoint {
+    pub games: u64,
+    pub wins: u64,
+    pub llr: f64
+}
+
+// The test after the match and its state after every chunk of games
+#[derive(Debug, PartialEq, Clone)]
+pub struct SprtResults {
+    pub test: Sprt,
+    // Games that ended without a winner, they are not part of the test
+    pub draws: u64,
+    pub trace: Vec<SprtPoint>
+}
+
+// Plays the first of two players against the second, swapping seats after every game, until the test decides or max_games
+// were played. The games of a chunk of report_every games are played in parallel and counted in their order until the test
+// decides, so the result does not depend on the number of threads. A draw is no evidence for either hypothesis and is left
+// out of the test
+pub struct SprtMatch<'a> {
+    registry: &'a PlayerRegistry,
+    pub players: Vec<PlayerSpec>,
+    pub ruleset: Ruleset,
+    pub test: Sprt,
+    pub max_games: usize,
+    pub report_every: usize,
+    pub runner: Runner
+}
+
+impl<'a> SprtMatch<'a> {
+    pub fn new(registry: &'a PlayerRegistry, players: Vec<PlayerSpec>, ruleset: Ruleset, test: Sprt) -> Self {
+        SprtMatch { registry, players, ruleset, test, max_games: 100000, report_every: 100, runner: Runner::new() }
+    }
+
+    // Whether the first player won a game, None for a draw
+    fn play_game(&self, game: usize, rng: &mut impl Rng) -> Result<Option<bool>, RegistryError> {
+        // The players take turns at starting
+        let first = game % 2;
+        let specs = match first {
+            0 => [&self.players[0], &self.players[1]],
+            _ => [&self.players[1], &self.players[0]]
+        };
+        let players = specs.iter().map(|s| self.registry.build(s, rng.gen())).collect::<Result<Vec<Box<dyn Player>>, _>>()?;
+
+        let mut g = SkipBoGame::with_seed(self.ruleset, 2, rng.gen());
+        g.play_out(&players);
+        Ok(match g.winner {
+            -1 => None,
+            w => Some(w == first as i8)
+        })
+    }
+
+    // Play until the test decides, report is called with the state of the test after every chunk
+    pub fn run(&self, seed: u64, mut report: impl FnMut(&SprtPoint)) -> Result<SprtResults, RegistryError> {
+        assert!(self.players.len() == 2, "the test compares 2 players");
+        assert!(self.report_every > 0, "a chunk needs at least one game");
+        let mut results = SprtResults { test: self.test, draws: 0, trace: Vec::new() };
+
+        let mut chunk = 0;
+        while results.test.decision() == Decision::Continue && chunk * self.report_every < self.max_games {
+            let first = chunk * self.report_every;
+            let games = self.report_every.min(self.max_games - first);
+            let outcomes = self.runner.run(games, job_seed(seed, chunk), |n, rng| self.play_game(first + n, rng), || {});
+            chunk += 1;
+
+            for outcome in outcomes {
+                match outcome? {
+                    Some(won) => results.test.record(won),
+                    None => results.draws += 1
+                }
+                if results.test.decision() != Decision::Continue {
+                    break;
+                }
+            }
+
+            let point = SprtPoint { games: results.test.games(), wins: results.test.wins, llr: results.test.llr() };
+            report(&point);
+            results.trace.push(point);
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    fn assert_close(found: f64, expected: f64) {
+        assert!((found - expected).abs() < 1e-9, "expected {}, found {}", expected, found);
+    }
+
+    #[test]
+    fn bounds_and_llr() {
+        let mut test = Sprt::new(0.5, 0.55, 0.05, 0.05);
+        let (lower, upper) = test.bounds();
+        assert_close(lower, (0.05_f64 / 0.95).ln());
+        assert_close(upper, (0.95_f64 / 0.05).ln());
+        assert_close(Sprt::new(0.5, 0.55, 0.05, 0.1).bounds().0, (0.1_f64 / 0.95).ln());
+        assert_close(Sprt::new(0.5, 0.55, 0.05, 0.1).bounds().1, (0.9_f64 / 0.05).ln());
+
+        assert_close(test.llr(), 0.0);
+        test.record(true);
+        assert_close(test.llr(), 1.1_f64.ln());
+        test.record(false);
+        assert_close(test.llr(), 1.1_f64.ln() + 0.9_f64.ln());
+        assert_eq!(test.games(), 2);
+    }
+
+    #[test]
+    fn stops_at_the_bounds() {
+        // Every win adds ln(1.1) and the upper bound ln(19) is reached with the 31st
+        let mut test = Sprt::new(0.5, 0.55, 0.05, 0.05);
+        for _ in 0..30 {
+            test.record(true);
+            assert_eq!(test.decision(), Decision::Continue);
+        }
+        test.record(true);
+        assert_eq!(test.decision(), Decision::AcceptH1);
+
+        // Every loss adds ln(0.9), the lower bound is reached with the 28th
+        let mut test = Sprt::new(0.5, 0.55, 0.05, 0.05);
+        for _ in 0..27 {
+            test.record(false);
+            assert_eq!(test.decision(), Decision::Continue);
+        }
+        test.record(false);
+        assert_eq!(test.decision(), Decision::AcceptH0);
+
+        // Winning every other game is closer to p0, a pair of games adds ln(0.99)
+        let mut test = Sprt::new(0.5, 0.55, 0.05, 0.05);
+        while test.decision() == Decision::Continue {
+            test.record(test.games().is_multiple_of(2));
+        }
+        assert_eq!(test.decision(), Decision::AcceptH0);
+        assert_eq!(test.games(), 586);
+    }
+
+    #[test]
+    fn error_rates_stay_near_alpha_and_beta() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut accepted = |p: f64, decision: Decision| {
+            (0..1000).filter(|_| {
+                let mut test = Sprt::new(0.5, 0.6, 0.05, 0.05);
+                while test.decision() == Decision::Continue {
+                    test.record(rng.gen_bool(p));
+                }
+                test.decision() == decision
+            }).count()
+        };
+
+        // The bounds are conservative, the real error rates are at most alpha and beta up to the noise of 1000 tests
+        assert!(accepted(0.5, Decision::AcceptH1) <= 65);
+        assert!(accepted(0.6, Decision::AcceptH0) <= 65);
+    }
+
+    #[test]
+    fn match_does_not_depend_on_the_threads() {
+        let registry = PlayerRegistry::new();
+        let players = vec!["good".parse().unwrap(), "simple".parse().unwrap()];
+        let mut m = SprtMatch::new(&registry, players, Ruleset::official(), Sprt::new(0.5, 0.55, 0.05, 0.05));
+        m.report_every = 7;
+
+        m.runner = Runner::with_threads(1);
+        let one = m.run(3, |_| {}).unwrap();
+        m.runner = Runner::with_threads(4);
+        let mut reported = Vec::new();
+        let four = m.run(3, |point| reported.push(*point)).unwrap();
+
+        assert_eq!(one, four);
+        assert_eq!(reported, four.trace);
+        assert_ne!(four.test.decision(), Decision::Continue);
+        assert_eq!(four.trace.last().unwrap().games, four.test.games());
+    }
+}
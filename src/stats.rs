@@ -0,0 +1,340 @@
+use std::{fmt, str::FromStr};
+
+use serde::Serialize;
+
+// Logarithm of the gamma function, Lanczos approximation with g = 7
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8, 771.323_428_777_653_1, -176.615_029_162_140_6,
+        12.507_343_278_686_905, -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7
+    ];
+
+    if x < 0.5 {
+        // Reflection formula
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS[1..].iter().enumerate().fold(COEFFICIENTS[0], |s, (i, c)| s + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+// Continued fraction of the incomplete beta function, evaluated with the modified Lentz method
+fn beta_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+
+    // Large parameters need about the square root of the larger one in steps
+    for m in 1..100_000 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+
+        for numerator in [m * (b - m) * x / ((a + m2 - 1.0) * (a + m2)), -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0))] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+
+        if (d * c - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    h
+}
+
+// Regularized incomplete beta function I_x(a, b), the CDF of the beta distribution
+pub fn incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly on this side of the mean, the other side follows from symmetry
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_fraction(1.0 - x, b, a) / b
+    }
+}
+
+// The x with I_x(a, b) = p, found by bisection
+pub fn beta_quantile(p: f64, a: f64, b: f64) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if incomplete_beta(mid, a, b) < p {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// CDF of the standard normal distribution
+pub fn normal_cdf(z: f64) -> f64 {
+    // Complementary error function with a fractional error below 1.2e-7
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.5 * x);
+    let erfc = t * (-x * x - 1.265_512_23 + t * (1.000_023_68 + t * (0.374_091_96 + t * (0.096_784_18 + t * (-0.186_288_06
+        + t * (0.278_868_07 + t * (-1.135_203_98 + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))))).exp();
+
+    if z >= 0.0 { 1.0 - erfc / 2.0 } else { erfc / 2.0 }
+}
+
+// Inverse of the CDF of the standard normal distribution, Acklam's approximation with a relative error below 1.2e-9
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969_683_028_665_376e1, 2.209_460_984_245_205e2, -2.759_285_104_469_687e2, 1.383_577_518_672_69e2, -3.066_479_806_614_716e1, 2.506_628_277_459_239];
+    const B: [f64; 5] = [-5.447_609_879_822_406e1, 1.615_858_368_580_409e2, -1.556_989_798_598_866e2, 6.680_131_188_771_972e1, -1.328_068_155_288_572e1];
+    const C: [f64; 6] = [-7.784_894_002_430_293e-3, -3.223_964_580_411_365e-1, -2.400_758_277_161_838, -2.549_732_539_343_734, 4.374_664_141_464_968, 2.938_163_982_698_783];
+    const D: [f64; 4] = [7.784_695_709_041_462e-3, 3.224_671_290_700_398e-1, 2.445_134_137_142_996, 3.754_408_661_907_416];
+    const LOW: f64 = 0.02425;
+
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+
+    let tail = |q: f64| (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5]) / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0);
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+// How the confidence interval of a win rate is computed
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Interval {
+    // Score interval of the normal approximation, close to the nominal level even for few games or extreme rates
+    Wilson,
+    // Exact interval from the binomial distribution, covers at least the nominal level and is a bit wider
+    ClopperPearson
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Interval::Wilson => write!(f, "wilson"),
+            Interval::ClopperPearson => write!(f, "clopper-pearson")
+        }
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wilson" => Ok(Interval::Wilson),
+            "clopper-pearson" => Ok(Interval::ClopperPearson),
+            _ => Err(format!("unknown interval \"{}\", expected wilson or clopper-pearson", s))
+        }
+    }
+}
+
+// Games won out of games played
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub struct WinRate {
+    pub wins: u64,
+    pub games: u64
+}
+
+impl WinRate {
+    pub fn new(wins: u64, games: u64) -> Self {
+        assert!(wins <= games, "more games won than played");
+        WinRate { wins, games }
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+
+    // Standard error of the win rate
+    pub fn standard_error(&self) -> f64 {
+        let p = self.rate();
+        (p * (1.0 - p) / self.games.max(1) as f64).sqrt()
+    }
+
+    // Lowest and highest win rate that are consistent with the games at the confidence level, ex: 0.95
+    pub fn interval(&self, method: Interval, confidence: f64) -> (f64, f64) {
+        if self.games == 0 {
+            return (0.0, 1.0);
+        }
+
+        let alpha = 1.0 - confidence;
+        let (k, n) = (self.wins as f64, self.games as f64);
+        match method {
+            Interval::Wilson => {
+                let z = normal_quantile(1.0 - alpha / 2.0);
+                let p = self.rate();
+                let center = (p + z * z / (2.0 * n)) / (1.0 + z * z / n);
+                let half = z / (1.0 + z * z / n) * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt();
+                ((center - half).max(0.0), (center + half).min(1.0))
+            }
+            Interval::ClopperPearson => {
+                let lower = if self.wins == 0 { 0.0 } else { beta_quantile(alpha / 2.0, k, n - k + 1.0) };
+                let upper = if self.wins == self.games { 1.0 } else { beta_quantile(1.0 - alpha / 2.0, k + 1.0, n - k) };
+                (lower, upper)
+            }
+        }
+    }
+}
+
+// The hypothesis a test looks for evidence of, against the null hypothesis of no difference
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Alternative {
+    // The first player wins more often
+    Greater,
+    Less,
+    // The players differ in either direction
+    TwoSided
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct TestResult {
+    // The win rate for the binomial test, z for the two-proportion test
+    pub statistic: f64,
+    // Probability of a result at least this extreme if the null hypothesis holds
+    pub p_value: f64
+}
+
+impl TestResult {
+    // Whether the null hypothesis is rejected at the confidence level, ex: 0.95
+    pub fn significant(&self, confidence: f64) -> bool {
+        self.p_value < 1.0 - confidence
+    }
+}
+
+// Exact test of a win rate against the expected rate p0, ex: whether a player beats the other in more than half of their
+// games (p0 = 0.5). The two-sided p-value doubles the smaller tail
+pub fn binomial_test(win_rate: WinRate, p0: f64, alternative: Alternative) -> TestResult {
+    let (k, n) = (win_rate.wins as f64, win_rate.games as f64);
+    // P(X >= k) and P(X <= k) for X ~ Binomial(n, p0)
+    let upper = if win_rate.wins == 0 { 1.0 } else { incomplete_beta(p0, k, n - k + 1.0) };
+    let lower = if win_rate.wins == win_rate.games { 1.0 } else { 1.0 - incomplete_beta(p0, k + 1.0, n - k) };
+
+    let p_value = match alternative {
+        Alternative::Greater => upper,
+        Alternative::Less => lower,
+        Alternative::TwoSided => (2.0 * upper.min(lower)).min(1.0)
+    };
+    TestResult { statistic: win_rate.rate(), p_value }
+}
+
+// Exact sign test of two players measured on the same deals (the exact McNemar test): only the deals that exactly one of
+// them won tell them apart, the first player won `first` and the second `second` of those
+pub fn sign_test(first: u64, second: u64, alternative: Alternative) -> TestResult {
+    binomial_test(WinRate::new(first, first + second), 0.5, alternative)
+}
+
+// Test whether two win rates measured in separate games differ, with the pooled normal approximation
+pub fn two_proportion_test(a: WinRate, b: WinRate, alternative: Alternative) -> TestResult {
+    let (n1, n2) = (a.games.max(1) as f64, b.games.max(1) as f64);
+    let pooled = (a.wins + b.wins) as f64 / (n1 + n2);
+    let se = (pooled * (1.0 - pooled) * (1.0 / n1 + 1.0 / n2)).sqrt();
+    let z = if se == 0.0 { 0.0 } else { (a.rate() - b.rate()) / se };
+
+    let p_value = match alternative {
+        Alternative::Greater => 1.0 - normal_cdf(z),
+        Alternative::Less => normal_cdf(z),
+        Alternative::TwoSided => 2.0 * (1.0 - normal_cdf(z.abs()))
+    };
+    TestResult { statistic: z, p_value }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(found: f64, expected: f64) {
+        assert!((found - expected).abs() < 1e-6, "expected {}, found {}", expected, found);
+    }
+
+    fn assert_interval(found: (f64, f64), expected: (f64, f64)) {
+        assert_close(found.0, expected.0);
+        assert_close(found.1, expected.1);
+    }
+
+    #[test]
+    fn normal_distribution() {
+        assert_close(normal_cdf(0.0), 0.5);
+        assert_close(normal_cdf(1.96), 0.975_002_104_9);
+        assert_close(normal_cdf(-1.96), 0.024_997_895_1);
+        assert_close(normal_quantile(0.975), 1.959_963_984_5);
+        assert_close(normal_quantile(0.5), 0.0);
+        assert_close(normal_quantile(0.001), -3.090_232_306_2);
+    }
+
+    #[test]
+    fn wilson_interval() {
+        assert_interval(WinRate::new(5, 10).interval(Interval::Wilson, 0.95), (0.236_593_090_5, 0.763_406_909_5));
+        assert_interval(WinRate::new(81, 100).interval(Interval::Wilson, 0.95), (0.722_211_546_2, 0.874_852_485_0));
+        assert_interval(WinRate::new(0, 20).interval(Interval::Wilson, 0.95), (0.0, 0.161_125_158_1));
+        assert_eq!(WinRate::new(0, 0).interval(Interval::Wilson, 0.95), (0.0, 1.0));
+    }
+
+    #[test]
+    fn clopper_pearson_interval() {
+        assert_interval(WinRate::new(5, 10).interval(Interval::ClopperPearson, 0.95), (0.187_086_028_4, 0.812_913_971_6));
+        assert_interval(WinRate::new(81, 100).interval(Interval::ClopperPearson, 0.95), (0.719_302_042_0, 0.881_556_803_9));
+        // Without a win or a loss the bound is the root of alpha / 2
+        assert_interval(WinRate::new(0, 10).interval(Interval::ClopperPearson, 0.95), (0.0, 1.0 - 0.025_f64.powf(0.1)));
+        assert_interval(WinRate::new(10, 10).interval(Interval::ClopperPearson, 0.95), (0.025_f64.powf(0.1), 1.0));
+    }
+
+    #[test]
+    fn binomial_p_values() {
+        // P(X >= 7) for X ~ Binomial(10, 0.5) is 176 / 1024
+        assert_close(binomial_test(WinRate::new(7, 10), 0.5, Alternative::Greater).p_value, 176.0 / 1024.0);
+        assert_close(binomial_test(WinRate::new(7, 10), 0.5, Alternative::Less).p_value, 1.0 - 56.0 / 1024.0);
+        assert_close(binomial_test(WinRate::new(7, 10), 0.5, Alternative::TwoSided).p_value, 352.0 / 1024.0);
+        assert_close(binomial_test(WinRate::new(0, 10), 0.5, Alternative::Less).p_value, 1.0 / 1024.0);
+        assert_close(binomial_test(WinRate::new(0, 10), 0.5, Alternative::Greater).p_value, 1.0);
+        assert_close(binomial_test(WinRate::new(60, 100), 0.5, Alternative::Greater).p_value, 0.028_443_966_8);
+        assert_close(binomial_test(WinRate::new(60, 100), 0.5, Alternative::TwoSided).p_value, 0.056_887_933_6);
+        assert_close(binomial_test(WinRate::new(2, 20), 0.3, Alternative::Less).p_value, 0.035_483_132_3);
+        assert_close(binomial_test(WinRate::new(5, 10), 0.5, Alternative::TwoSided).p_value, 1.0);
+    }
+
+    #[test]
+    fn sign_test_counts_the_deals_won_by_one_player() {
+        assert_close(sign_test(7, 3, Alternative::TwoSided).p_value, 352.0 / 1024.0);
+        assert_close(sign_test(3, 7, Alternative::Greater).p_value, 1.0 - 56.0 / 1024.0);
+        assert_close(sign_test(10, 0, Alternative::Greater).p_value, 1.0 / 1024.0);
+    }
+
+    #[test]
+    fn two_proportion_p_values() {
+        let test = two_proportion_test(WinRate::new(60, 100), WinRate::new(45, 100), Alternative::TwoSided);
+        assert_close(test.statistic, 2.123_976_976_2);
+        assert_close(test.p_value, 0.033_672_068_9);
+        assert_close(two_proportion_test(WinRate::new(60, 100), WinRate::new(45, 100), Alternative::Greater).p_value, 0.016_836_034_4);
+        assert!(test.significant(0.95) && !test.significant(0.99));
+    }
+}
@@ -0,0 +1,337 @@
+use std::{fmt, str::FromStr};
+
+use rand::Rng;
+use serde::Serialize;
+
+use crate::{game::{Game, SkipBoGame}, players::{player::Player, registry::{PlayerRegistry, PlayerSpec, RegistryError}}, ruleset::Ruleset, runner::{Runner, job_seed}, stats::normal_cdf};
+
+// Skill of a player as a normal distribution: mu is the estimate, sigma how uncertain it is
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct Rating {
+    pub mu: f64,
+    pub sigma: f64
+}
+
+impl Rating {
+    // The skill the player has with about 99% probability, players are ranked by it
+    pub fn conservative(&self) -> f64 {
+        self.mu - 3.0 * self.sigma
+    }
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating { mu: 25.0, sigma: 25.0 / 3.0 }
+    }
+}
+
+// TrueSkill ratings of games with one winner. A game at a larger table counts as a win of the winner against every other
+// player of the table, all computed from the ratings before the game
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TrueSkill {
+    // Spread of the performance in a single game around the skill
+    pub beta: f64,
+    // Uncertainty added before every game, so the ratings can follow players whose skill changes
+    pub tau: f64
+}
+
+impl Default for TrueSkill {
+    fn default() -> Self {
+        TrueSkill { beta: 25.0 / 6.0, tau: 25.0 / 300.0 }
+    }
+}
+
+impl TrueSkill {
+    // Update the ratings of the players of a table after a game, players are indices into ratings
+    pub fn update(&self, ratings: &mut [Rating], table: &[usize], winner: usize) {
+        let before: Vec<Rating> = table.iter().map(|p| Rating { sigma: (ratings[*p].sigma.powi(2) + self.tau.powi(2)).sqrt(), ..ratings[*p] }).collect();
+        let w = table.iter().position(|p| *p == winner).expect("the winner sits at the table");
+
+        let mut winner_mu = before[w].mu;
+        let mut winner_variance = before[w].sigma.powi(2);
+        for (l, loser) in before.iter().enumerate().filter(|(l, _)| *l != w) {
+            let (sw2, sl2) = (before[w].sigma.powi(2), loser.sigma.powi(2));
+            let c = (2.0 * self.beta.powi(2) + sw2 + sl2).sqrt();
+            let t = (before[w].mu - loser.mu) / c;
+            // Mean and variance correction of a normal truncated to the observed winner
+            let pdf = (-t * t / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt();
+            let v = pdf / normal_cdf(t).max(1e-300);
+            let vw = (v * (v + t)).min(1.0 - 1e-9);
+
+            winner_mu += sw2 / c * v;
+            winner_variance *= 1.0 - sw2 / (c * c) * vw;
+            ratings[table[l]] = Rating { mu: loser.mu - sl2 / c * v, sigma: (sl2 * (1.0 - sl2 / (c * c) * vw)).sqrt() };
+        }
+        ratings[winner] = Rating { mu: winner_mu, sigma: winner_variance.sqrt() };
+    }
+}
+
+// How the tables of a tournament are drawn
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Pairing {
+    // Every group of players that fills a table meets once
+    RoundRobin,
+    // Every round seats players of similar rating together, for the given number of rounds. Pairs avoid opponents they
+    // already met while they can. Players that do not fill a table sit the round out, the ones that sat out least often first
+    Swiss { rounds: usize }
+}
+
+impl fmt::Display for Pairing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pairing::RoundRobin => write!(f, "round-robin"),
+            Pairing::Swiss { rounds } => write!(f, "swiss:{}", rounds)
+        }
+    }
+}
+
+// "round-robin", "swiss" (5 rounds) or "swiss:rounds"
+impl FromStr for Pairing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "round-robin" => Ok(Pairing::RoundRobin),
+            None if s == "swiss" => Ok(Pairing::Swiss { rounds: 5 }),
+            Some(("swiss", rounds)) => rounds.parse().map(|rounds| Pairing::Swiss { rounds }).map_err(|_| format!("invalid number of rounds \"{}\"", rounds)),
+            _ => Err(format!("unknown pairing \"{}\", expected round-robin or swiss:rounds", s))
+        }
+    }
+}
+
+// Every group of size k of the players 0..n in lexicographic order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut result = Vec::new();
+    let mut group: Vec<usize> = (0..k).collect();
+    if k == 0 || k > n {
+        return result;
+    }
+
+    loop {
+        result.push(group.clone());
+        // Advance the last position that can still move
+        let Some(i) = (0..k).rev().find(|i| group[*i] < n - k + i) else { return result };
+        group[i] += 1;
+        for j in i + 1..k {
+            group[j] = group[j - 1] + 1;
+        }
+    }
+}
+
+// Results of a tournament so far
+#[derive(Debug, Clone, Serialize)]
+pub struct Standings {
+    pub ratings: Vec<Rating>,
+    // Games played and won by every player
+    pub games: Vec<u64>,
+    pub won: Vec<u64>,
+    // met[a][b]: games a and b played at the same table, wins[a][b]: games of those won by a
+    pub met: Vec<Vec<u64>>,
+    pub wins: Vec<Vec<u64>>
+}
+
+impl Standings {
+    fn new(players: usize) -> Self {
+        Standings {
+            ratings: vec![Rating::default(); players],
+            games: vec![0; players],
+            won: vec![0; players],
+            met: vec![vec![0; players]; players],
+            wins: vec![vec![0; players]; players]
+        }
+    }
+
+    // A draw counts as a game for every player of the table, but as a win for nobody and leaves the ratings as they are
+    fn record(&mut self, trueskill: &TrueSkill, table: &[usize], winner: Option<usize>) {
+        for a in table {
+            self.games[*a] += 1;
+            for b in table.iter().filter(|b| *b != a) {
+                self.met[*a][*b] += 1;
+            }
+        }
+        let Some(winner) = winner else { return };
+        self.won[winner] += 1;
+        for b in table.iter().filter(|b| **b != winner) {
+            self.wins[winner][*b] += 1;
+        }
+        trueskill.update(&mut self.ratings, table, winner);
+    }
+
+    // Players from the highest to the lowest conservative rating
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.ratings.len()).collect();
+        order.sort_by(|a, b| self.ratings[*b].conservative().total_cmp(&self.ratings[*a].conservative()));
+        order
+    }
+}
+
+// Plays the registered players against each other at tables of a number of seats, in parallel on the runner. Every table plays
+// a number of games, the players move one seat on after every game. The ratings are updated in the order the games were
+// scheduled, so the results only depend on the seed
+pub struct Tournament<'a> {
+    registry: &'a PlayerRegistry,
+    pub players: Vec<PlayerSpec>,
+    pub ruleset: Ruleset,
+    pub seats: usize,
+    pub pairing: Pairing,
+    // Games played by a table in a round
+    pub games: usize,
+    pub trueskill: TrueSkill,
+    pub runner: Runner
+}
+
+impl<'a> Tournament<'a> {
+    pub fn new(registry: &'a PlayerRegistry, players: Vec<PlayerSpec>, ruleset: Ruleset) -> Self {
+        Tournament { registry, players, ruleset, seats: 2, pairing: Pairing::RoundRobin, games: 100, trueskill: TrueSkill::default(), runner: Runner::new() }
+    }
+
+    fn rounds(&self) -> usize {
+        match self.pairing {
+            Pairing::RoundRobin => 1,
+            Pairing::Swiss { rounds } => rounds
+        }
+    }
+
+    fn tables_per_round(&self) -> usize {
+        match self.pairing {
+            Pairing::RoundRobin => combinations(self.players.len(), self.seats).len(),
+            Pairing::Swiss { .. } => self.players.len() / self.seats
+        }
+    }
+
+    // Games of the whole tournament, ex: for a progress bar
+    pub fn total_games(&self) -> usize {
+        self.rounds() * self.tables_per_round() * self.games
+    }
+
+    // The tables of a Swiss round: the players by rating, each pair takes the best rated opponent not met yet if there is one
+    fn swiss_tables(&self, standings: &Standings, byes: &mut [usize]) -> Vec<Vec<usize>> {
+        let mut order = standings.ranking();
+        // The players left over sit out: those with the fewest byes so far, of those the lowest rated
+        let mut sitting_out: Vec<usize> = order.iter().rev().copied().collect();
+        sitting_out.sort_by_key(|p| byes[*p]);
+        for p in sitting_out.into_iter().take(self.players.len() % self.seats) {
+            byes[p] += 1;
+            order.retain(|q| *q != p);
+        }
+
+        if self.seats > 2 {
+            return order.chunks(self.seats).map(|c| c.to_vec()).collect();
+        }
+
+        let mut tables = Vec::new();
+        while let Some(a) = order.first().copied() {
+            order.remove(0);
+            let b = order.iter().position(|b| standings.met[a][*b] == 0).unwrap_or(0);
+            tables.push(vec![a, order.remove(b)]);
+        }
+        tables
+    }
+
+    // Play a round and record its games. The games are recorded in the order of the game numbers of the tables, so no table
+    // is rated after all the others
+    fn play_round(&self, tables: &[Vec<usize>], round: usize, seed: u64, standings: &mut Standings, done: &(impl Fn() + Sync)) -> Result<(), RegistryError> {
+        let results = self.runner.run(tables.len() * self.games, job_seed(seed, round), |job, rng| {
+            let (table, n) = (&tables[job % tables.len()], job / tables.len());
+            let seated: Vec<usize> = (0..self.seats).map(|s| table[(s + n) % self.seats]).collect();
+            let players = seated.iter().map(|p| self.registry.build(&self.players[*p], rng.gen())).collect::<Result<Vec<Box<dyn Player>>, _>>()?;
+
+            let mut game = SkipBoGame::with_seed(self.ruleset, self.seats as i8, rng.gen());
+            game.play_out(&players);
+            Ok((game.winner >= 0).then(|| seated[game.winner as usize]))
+        }, done);
+
+        for (job, winner) in results.into_iter().enumerate() {
+            standings.record(&self.trueskill, &tables[job % tables.len()], winner?);
+        }
+        Ok(())
+    }
+
+    // Play every round, done is called after every game
+    pub fn run(&self, seed: u64, done: impl Fn() + Sync) -> Result<Standings, RegistryError> {
+        assert!(self.seats >= 2 && self.seats <= self.players.len(), "a table needs 2 seats and the players to fill them");
+        let mut standings = Standings::new(self.players.len());
+        let mut byes = vec![0; self.players.len()];
+
+        for round in 0..self.rounds() {
+            let tables = match self.pairing {
+                Pairing::RoundRobin => combinations(self.players.len(), self.seats),
+                Pairing::Swiss { .. } => self.swiss_tables(&standings, &mut byes)
+            };
+            self.play_round(&tables, round, seed, &mut standings, &done)?;
+        }
+
+        Ok(standings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binomial(n: usize, k: usize) -> usize {
+        (0..k).fold(1, |c, i| c * (n - i) / (i + 1))
+    }
+
+    #[test]
+    fn combinations_are_unique_and_complete() {
+        for n in 0..7 {
+            for k in 1..=n {
+                let groups = combinations(n, k);
+                assert_eq!(groups.len(), binomial(n, k), "{} of {}", k, n);
+                for g in &groups {
+                    assert_eq!(g.len(), k);
+                    assert!(g.windows(2).all(|w| w[0] < w[1]) && g[k - 1] < n);
+                }
+                // Lexicographic order makes every group differ from the one before
+                assert!(groups.windows(2).all(|w| w[0] < w[1]));
+            }
+        }
+        assert!(combinations(3, 4).is_empty());
+        assert!(combinations(3, 0).is_empty());
+    }
+
+    #[test]
+    fn winner_gains_and_every_sigma_shrinks() {
+        let trueskill = TrueSkill::default();
+        let mut ratings = vec![Rating::default(), Rating { mu: 30.0, sigma: 4.0 }, Rating { mu: 20.0, sigma: 6.0 }, Rating::default()];
+        let before = ratings.clone();
+        trueskill.update(&mut ratings, &[0, 1, 2], 2);
+
+        assert!(ratings[2].mu > before[2].mu);
+        assert!(ratings[0].mu < before[0].mu && ratings[1].mu < before[1].mu);
+        for p in 0..3 {
+            assert!(ratings[p].sigma < before[p].sigma, "sigma of {} grew from {} to {}", p, before[p].sigma, ratings[p].sigma);
+        }
+        // Only the players of the table are rated
+        assert_eq!(ratings[3], before[3]);
+    }
+
+    #[test]
+    fn swiss_byes_go_to_the_lowest_rated_with_the_fewest_byes() {
+        let registry = PlayerRegistry::new();
+        let players = vec!["good".parse().unwrap(); 5];
+        let mut tournament = Tournament::new(&registry, players, Ruleset::official());
+        tournament.pairing = Pairing::Swiss { rounds: 3 };
+        let mut standings = Standings::new(5);
+        for (p, mu) in [30.0, 25.0, 28.0, 15.0, 20.0].into_iter().enumerate() {
+            standings.ratings[p].mu = mu;
+        }
+
+        let seated = |tables: &Vec<Vec<usize>>| -> Vec<usize> {
+            let mut seated: Vec<usize> = tables.iter().flatten().copied().collect();
+            seated.sort_unstable();
+            seated
+        };
+        let mut byes = vec![0; 5];
+        let tables = tournament.swiss_tables(&standings, &mut byes);
+        assert_eq!(seated(&tables), vec![0, 1, 2, 4]);
+        assert_eq!(byes, vec![0, 0, 0, 1, 0]);
+
+        // Player 3 sat out already, the next lowest rated sits out now
+        let tables = tournament.swiss_tables(&standings, &mut byes);
+        assert_eq!(seated(&tables), vec![0, 1, 2, 3]);
+        assert_eq!(byes, vec![0, 0, 0, 1, 1]);
+    }
+}
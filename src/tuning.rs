@@ -0,0 +1,172 @@
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::Serialize;
+
+use crate::{game::{Game, SkipBoGame}, players::{good_player::GoodPlayer, registry::{PlayerRegistry, PlayerSpec, RegistryError}}, evaluation::{LinearEvaluator, Features}, ruleset::Ruleset, runner::Runner};
+
+// A numeric parameter of a player spec and the range the tuner searches it in. The tuner perturbs it by multiples of step,
+// parameters with different units are compared in steps
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct TunedParameter {
+    pub name: String,
+    pub initial: f64,
+    pub step: f64,
+    pub min: f64,
+    pub max: f64,
+    // Rounded to a whole number before it is passed to the player
+    pub integer: bool
+}
+
+impl TunedParameter {
+    fn clamp(&self, value: f64) -> f64 {
+        value.clamp(self.min, self.max)
+    }
+
+    // The value as it is written into the spec
+    fn format(&self, value: f64) -> String {
+        match self.integer {
+            true => format!("{}", self.clamp(value).round()),
+            false => format!("{}", (self.clamp(value) * 1000.0).round() / 1000.0)
+        }
+    }
+}
+
+// The evaluator weights of a player, starting from the weights its spec sets. STACK cards are left at their weight,
+// only the ratio between the weights changes the decisions
+fn weight_parameters(spec: &PlayerSpec, default: LinearEvaluator) -> Result<Vec<TunedParameter>, RegistryError> {
+    let weights = spec.evaluator(default)?.weights.to_vec();
+    Ok(Features::NAMES.iter().zip(weights).skip(1).map(|(name, initial)| TunedParameter { name: name.to_string(), initial, step: 0.2, min: -10.0, max: 10.0, integer: false }).collect())
+}
+
+// The parameters worth tuning of the players of this crate, starting from the values the spec sets. Empty if the player has none
+pub fn default_parameters(spec: &PlayerSpec) -> Result<Vec<TunedParameter>, RegistryError> {
+    match spec.name.as_str() {
+        "good" => {
+            let player = GoodPlayer::default();
+            Ok(vec![
+                TunedParameter { name: "help_distance".to_string(), initial: spec.param("help_distance", player.help_distance as f64)?, step: 1.0, min: 0.0, max: 11.0, integer: true },
+                TunedParameter { name: "block_distance".to_string(), initial: spec.param("block_distance", player.block_distance as f64)?, step: 1.0, min: 1.0, max: 12.0, integer: true }
+            ])
+        }
+        "planner" => weight_parameters(spec, LinearEvaluator::default()),
+        "mcts" | "ismcts" => weight_parameters(spec, LinearEvaluator::cards_left()),
+        _ => Ok(Vec::new())
+    }
+}
+
+// Tunes the parameters of a player with SPSA (simultaneous perturbation stochastic approximation): every iteration moves all
+// parameters one step at random in either direction, plays the two resulting players against the opponents on the same deals
+// and moves the parameters towards the one that won more often. Two batches of games per iteration are enough for any
+// number of parameters and the same deals for both keep the noise of the comparison low
+pub struct Tuner<'a> {
+    registry: &'a PlayerRegistry,
+    spec: PlayerSpec,
+    parameters: Vec<TunedParameter>,
+    opponents: Vec<PlayerSpec>,
+    ruleset: Ruleset,
+    // Current value of every parameter
+    pub values: Vec<f64>,
+    // Games played by each of the two players of an iteration
+    pub games: usize,
+    // Size of the first update in steps per unit of win rate difference, it shrinks over the iterations
+    pub learning_rate: f64,
+    // Size of the first perturbation in steps, it shrinks over the iterations
+    pub perturbation: f64,
+    // Plays the games of a batch in parallel
+    pub runner: Runner,
+    // Iterations planned, the updates shrink more slowly during the first tenth of them
+    iterations: usize,
+    iteration: usize,
+    rng: StdRng
+}
+
+impl<'a> Tuner<'a> {
+    // The tuned player sits at every seat in turn, the opponents take the other seats in their order
+    pub fn new(registry: &'a PlayerRegistry, spec: PlayerSpec, parameters: Vec<TunedParameter>, opponents: Vec<PlayerSpec>, ruleset: Ruleset, iterations: usize, seed: u64) -> Self {
+        Tuner {
+            registry,
+            spec,
+            values: parameters.iter().map(|p| p.initial).collect(),
+            parameters,
+            opponents,
+            ruleset,
+            games: 200,
+            learning_rate: 20.0,
+            perturbation: 1.0,
+            runner: Runner::new(),
+            iterations,
+            iteration: 0,
+            rng: StdRng::seed_from_u64(seed)
+        }
+    }
+
+    pub fn parameters(&self) -> &[TunedParameter] {
+        &self.parameters
+    }
+
+    // The spec of the player with the given parameter values, ex: "good:block_distance=5:help_distance=2"
+    pub fn spec_with(&self, values: &[f64]) -> PlayerSpec {
+        let mut spec = self.spec.clone();
+        for (p, v) in self.parameters.iter().zip(values) {
+            spec.params.insert(p.name.clone(), p.format(*v));
+        }
+        spec
+    }
+
+    pub fn spec(&self) -> PlayerSpec {
+        self.spec_with(&self.values)
+    }
+
+    // Seeds of new deals, measure different players on the same seeds to compare them
+    pub fn deals(&mut self, games: usize) -> Vec<u64> {
+        (0..games).map(|_| self.rng.gen()).collect()
+    }
+
+    // Whether the player won each of the deals against the opponents. The opponents get the same seeds for every player,
+    // so the results of two players can be compared deal by deal
+    pub fn results(&self, spec: &PlayerSpec, deals: &[u64]) -> Result<Vec<bool>, RegistryError> {
+        let seats = self.opponents.len() + 1;
+
+        let results = self.runner.run(deals.len(), 0, |n, rng| {
+            let seat = n % seats;
+            let mut specs = self.opponents.clone();
+            specs.insert(seat, spec.clone());
+            let players = specs.iter().map(|s| self.registry.build(s, rng.gen())).collect::<Result<Vec<_>, _>>()?;
+
+            let mut game = SkipBoGame::with_seed(self.ruleset, seats as i8, deals[n]);
+            game.play_out(&players);
+            Ok(game.winner == seat as i8)
+        }, || {});
+
+        results.into_iter().collect()
+    }
+
+    // Fraction of the deals the player won against the opponents
+    pub fn win_rate(&self, spec: &PlayerSpec, deals: &[u64]) -> Result<f64, RegistryError> {
+        let wins = self.results(spec, deals)?.into_iter().filter(|w| *w).count();
+        Ok(wins as f64 / deals.len().max(1) as f64)
+    }
+
+    // One iteration of SPSA, returns the win rates of the two perturbed players
+    pub fn step(&mut self) -> Result<(f64, f64), RegistryError> {
+        let k = self.iteration as f64;
+        let a = self.learning_rate / (k + 1.0 + self.iterations as f64 / 10.0).powf(0.602);
+        let c = self.perturbation / (k + 1.0).powf(0.101);
+        self.iteration += 1;
+
+        let directions: Vec<f64> = self.parameters.iter().map(|_| if self.rng.gen() { 1.0 } else { -1.0 }).collect();
+        let perturbed = |sign: f64| -> Vec<f64> {
+            self.parameters.iter().zip(&self.values).zip(&directions).map(|((p, v), d)| p.clamp(v + sign * c * p.step * d)).collect()
+        };
+        let (plus, minus) = (self.spec_with(&perturbed(1.0)), self.spec_with(&perturbed(-1.0)));
+
+        let deals = self.deals(self.games);
+        let (win_plus, win_minus) = (self.win_rate(&plus, &deals)?, self.win_rate(&minus, &deals)?);
+
+        // The gradient is estimated in steps, so every parameter moves by the same number of its steps
+        for ((value, p), d) in self.values.iter_mut().zip(&self.parameters).zip(&directions) {
+            *value = p.clamp(*value + a * (win_plus - win_minus) / (2.0 * c * d) * p.step);
+        }
+
+        Ok((win_plus, win_minus))
+    }
+}